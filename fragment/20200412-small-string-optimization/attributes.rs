@@ -0,0 +1,264 @@
+//! Attributes.
+
+use std::{error, fmt, iter::FusedIterator, slice};
+
+use smallvec::SmallVec;
+
+use crate::string::{IntoSmallString, SmallString};
+
+/// Name-value pair.
+type Attribute = (SmallString, SmallString);
+
+/// Number of attributes stored without heap allocation.
+const INLINE_ATTRIBUTES_CAPACITY: usize = 4;
+
+/// Backend list type.
+///
+/// This does not depend on the string backend: short lists are worth keeping
+/// inline whether the strings themselves are inlined or not.
+type BackendList = SmallVec<[Attribute; INLINE_ATTRIBUTES_CAPACITY]>;
+
+/// Insertion-ordered attributes.
+///
+/// Names are unique in a list.
+///
+/// This type does not expose `&mut SmallString`: to modify a value efficiently,
+/// take the ownership by `take()`, modify it, and `set()` it again.
+///
+/// Attributes lists are expected to be short, so lookup is done by linear search.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attributes {
+    /// Attributes.
+    inner: BackendList,
+}
+
+impl Attributes {
+    /// Creates a new empty attributes list.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the position of the attribute with the given name.
+    #[inline]
+    fn position(&self, name: &str) -> Option<usize> {
+        self.inner.iter().position(|(n, _)| n == name)
+    }
+
+    /// Returns the number of attributes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the list has no attributes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns `true` if the list has the attribute with the given name.
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Returns the value of the attribute with the given name.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|i| self.inner[i].1.as_str())
+    }
+
+    /// Sets the attribute value.
+    ///
+    /// If the attribute already exists, its value is replaced and its position is kept.
+    /// Otherwise, the attribute is appended.
+    ///
+    /// The name is converted into `SmallString` only if the attribute is appended.
+    pub fn set(&mut self, name: impl AsRef<str> + IntoSmallString, value: impl IntoSmallString) {
+        let value = value.into_small_string();
+        match self.position(name.as_ref()) {
+            Some(i) => self.inner[i].1 = value,
+            None => self.inner.push((name.into_small_string(), value)),
+        }
+    }
+
+    /// Appends the attribute if the list does not have an attribute with the same name.
+    ///
+    /// This is useful for parsers which should reject duplicate attributes.
+    pub fn insert(
+        &mut self,
+        name: impl AsRef<str> + IntoSmallString,
+        value: impl IntoSmallString,
+    ) -> Result<(), DuplicateAttributeError> {
+        if let Some(index) = self.position(name.as_ref()) {
+            return Err(DuplicateAttributeError { index });
+        }
+        self.inner
+            .push((name.into_small_string(), value.into_small_string()));
+
+        Ok(())
+    }
+
+    /// Removes the attribute with the given name, and returns its value.
+    #[inline]
+    pub fn take(&mut self, name: &str) -> Option<String> {
+        self.position(name).map(|i| self.inner.remove(i).1.into())
+    }
+
+    /// Removes the attribute with the given name, and returns `true` if it existed.
+    #[inline]
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.position(name) {
+            Some(i) => {
+                self.inner.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes all attributes.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    /// Returns an iterator of name-value pairs, in insertion order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Attributes {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator of attributes.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    /// Inner iterator.
+    inner: slice::Iter<'a, Attribute>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a str);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+/// Error on inserting an attribute which already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DuplicateAttributeError {
+    /// Position of the existing attribute.
+    index: usize,
+}
+
+impl DuplicateAttributeError {
+    /// Returns the position of the existing attribute with the same name.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl fmt::Display for DuplicateAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate attribute (already exists at {})", self.index)
+    }
+}
+
+impl error::Error for DuplicateAttributeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the attributes list with the given attributes.
+    fn attributes(attrs: &[(&str, &str)]) -> Attributes {
+        let mut list = Attributes::new();
+        for &(name, value) in attrs {
+            list.insert(name, value).unwrap();
+        }
+        list
+    }
+
+    #[test]
+    fn set_keeps_position() {
+        let mut list = attributes(&[("id", "a"), ("class", "b")]);
+        list.set("id", "c");
+        list.set(String::from("lang"), "en");
+        list.set(SmallString::from("class"), String::from("d"));
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            [("id", "c"), ("class", "d"), ("lang", "en")]
+        );
+    }
+
+    #[test]
+    fn insert_rejects_duplicates() {
+        let mut list = attributes(&[("id", "a"), ("class", "b"), ("lang", "en")]);
+        assert_eq!(list.insert("class", "c").unwrap_err().index(), 1);
+        assert_eq!(list.insert("lang", "ja").unwrap_err().index(), 2);
+        assert_eq!(list.get("class"), Some("b"));
+        assert_eq!(list.len(), 3);
+        assert!(list.insert("title", "t").is_ok());
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn take_and_remove() {
+        let mut list = attributes(&[("id", "a"), ("class", "b"), ("lang", "en")]);
+        assert_eq!(list.take("class").as_deref(), Some("b"));
+        assert_eq!(list.take("class"), None);
+        assert!(list.remove("id"));
+        assert!(!list.remove("id"));
+        assert_eq!(list.iter().collect::<Vec<_>>(), [("lang", "en")]);
+        list.clear();
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn iter_in_insertion_order() {
+        let names = ["z", "a", "m", "b", "y", "c"];
+        let mut list = Attributes::new();
+        for (i, &name) in names.iter().enumerate() {
+            list.set(name, i.to_string());
+        }
+        let iter = list.iter();
+        assert_eq!(iter.len(), names.len());
+        assert_eq!(iter.map(|(n, _)| n).collect::<Vec<_>>(), names);
+        assert_eq!(list.iter().next_back(), Some(("c", "5")));
+        assert_eq!((&list).into_iter().nth(2), Some(("m", "2")));
+    }
+}
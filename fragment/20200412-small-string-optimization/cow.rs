@@ -0,0 +1,240 @@
+//! Clone-on-write string.
+
+use std::{
+    borrow::{Borrow, Cow},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    ops,
+};
+
+use crate::string::SmallString;
+
+/// Clone-on-write string, with `SmallString` as the owned type.
+///
+/// This is `Cow<'a, str>` with `SmallString` instead of `String`.
+/// Comparison and hashing are done by the content, regardless of whether the
+/// value is borrowed or owned.
+#[derive(Debug, Clone)]
+pub enum SmallCow<'a> {
+    /// Borrowed string.
+    Borrowed(&'a str),
+    /// Owned string.
+    Owned(SmallString),
+}
+
+impl<'a> SmallCow<'a> {
+    /// Extracts a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match self {
+            SmallCow::Borrowed(s) => s,
+            SmallCow::Owned(s) => s.as_str(),
+        }
+    }
+
+    /// Returns `true` if the value is borrowed.
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            SmallCow::Borrowed(_) => true,
+            SmallCow::Owned(_) => false,
+        }
+    }
+
+    /// Returns `true` if the value is owned.
+    #[inline]
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Acquires a mutable reference to the owned form, cloning the borrowed string if necessary.
+    pub fn to_mut(&mut self) -> &mut SmallString {
+        if let SmallCow::Borrowed(s) = *self {
+            *self = SmallCow::Owned(s.into());
+        }
+        match self {
+            SmallCow::Owned(s) => s,
+            SmallCow::Borrowed(_) => unreachable!("Should never fail: the value is owned"),
+        }
+    }
+
+    /// Extracts the owned string, cloning the borrowed string if necessary.
+    #[inline]
+    pub fn into_owned(self) -> SmallString {
+        match self {
+            SmallCow::Borrowed(s) => s.into(),
+            SmallCow::Owned(s) => s,
+        }
+    }
+}
+
+impl Default for SmallCow<'_> {
+    #[inline]
+    fn default() -> Self {
+        SmallCow::Borrowed("")
+    }
+}
+
+impl ops::Deref for SmallCow<'_> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallCow<'_> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for SmallCow<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl Borrow<str> for SmallCow<'_> {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for SmallCow<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl PartialEq for SmallCow<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallCow<'_> {}
+
+impl PartialOrd for SmallCow<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallCow<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallCow<'_> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<'a> From<&'a str> for SmallCow<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        SmallCow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a String> for SmallCow<'a> {
+    #[inline]
+    fn from(s: &'a String) -> Self {
+        SmallCow::Borrowed(s)
+    }
+}
+
+impl<'a> From<&'a SmallString> for SmallCow<'a> {
+    #[inline]
+    fn from(s: &'a SmallString) -> Self {
+        SmallCow::Borrowed(s.as_str())
+    }
+}
+
+impl From<SmallString> for SmallCow<'_> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        SmallCow::Owned(s)
+    }
+}
+
+impl From<String> for SmallCow<'_> {
+    #[inline]
+    fn from(s: String) -> Self {
+        SmallCow::Owned(s.into())
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for SmallCow<'a> {
+    #[inline]
+    fn from(s: Cow<'a, str>) -> Self {
+        match s {
+            Cow::Borrowed(s) => SmallCow::Borrowed(s),
+            Cow::Owned(s) => SmallCow::Owned(s.into()),
+        }
+    }
+}
+
+impl<'a> From<SmallCow<'a>> for Cow<'a, str> {
+    #[inline]
+    fn from(s: SmallCow<'a>) -> Self {
+        match s {
+            SmallCow::Borrowed(s) => Cow::Borrowed(s),
+            SmallCow::Owned(s) => Cow::Owned(s.into()),
+        }
+    }
+}
+
+impl From<SmallCow<'_>> for SmallString {
+    #[inline]
+    fn from(s: SmallCow<'_>) -> Self {
+        s.into_owned()
+    }
+}
+
+impl From<SmallCow<'_>> for String {
+    #[inline]
+    fn from(s: SmallCow<'_>) -> Self {
+        match s {
+            SmallCow::Borrowed(s) => s.into(),
+            SmallCow::Owned(s) => s.into(),
+        }
+    }
+}
+
+macro_rules! impl_cmp {
+    ($other:ty) => {
+        impl PartialEq<$other> for SmallCow<'_> {
+            #[inline]
+            fn eq(&self, other: &$other) -> bool {
+                PartialEq::eq(self.as_str(), &other[..])
+            }
+        }
+
+        impl PartialEq<SmallCow<'_>> for $other {
+            #[inline]
+            fn eq(&self, other: &SmallCow<'_>) -> bool {
+                PartialEq::eq(&self[..], other.as_str())
+            }
+        }
+    };
+}
+
+impl_cmp!(str);
+impl_cmp!(&'_ str);
+impl_cmp!(String);
+impl_cmp!(SmallString);
+impl_cmp!(Cow<'_, str>);
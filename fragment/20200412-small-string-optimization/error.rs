@@ -0,0 +1,190 @@
+//! Errors.
+
+use std::{error, fmt, str::Utf8Error, string::FromUtf8Error};
+
+/// Maximum length of bytes stored inline.
+const INLINE_BYTES_CAPACITY: usize = std::mem::size_of::<Vec<u8>>() - 1;
+
+/// Bytes which can be stored inline.
+#[derive(Clone)]
+enum Bytes {
+    /// Bytes on the heap.
+    Heap(Vec<u8>),
+    /// Bytes stored inline.
+    Inline {
+        /// Buffer.
+        buf: [u8; INLINE_BYTES_CAPACITY],
+        /// Length.
+        len: u8,
+    },
+}
+
+impl Bytes {
+    /// Returns the bytes.
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Bytes::Heap(v) => v,
+            Bytes::Inline { buf, len } => &buf[..usize::from(*len)],
+        }
+    }
+}
+
+/// Error on converting bytes into a `SmallString`.
+///
+/// This is `std::string::FromUtf8Error` independent from the backend string type.
+/// The original bytes can be stored either on the heap or inline.
+#[derive(Clone)]
+pub struct SmallFromUtf8Error {
+    /// Original bytes.
+    bytes: Bytes,
+    /// Validation error.
+    error: Utf8Error,
+}
+
+impl SmallFromUtf8Error {
+    /// Creates a new error from the given bytes on the heap.
+    #[inline]
+    pub(crate) fn from_vec(bytes: Vec<u8>, error: Utf8Error) -> Self {
+        Self {
+            bytes: Bytes::Heap(bytes),
+            error,
+        }
+    }
+
+    /// Creates a new error from the given bytes, copying them inline if they fit.
+    pub(crate) fn from_slice(bytes: &[u8], error: Utf8Error) -> Self {
+        if bytes.len() > INLINE_BYTES_CAPACITY {
+            return Self::from_vec(bytes.to_owned(), error);
+        }
+
+        let mut buf = [0; INLINE_BYTES_CAPACITY];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Self {
+            bytes: Bytes::Inline {
+                buf,
+                len: bytes.len() as u8,
+            },
+            error,
+        }
+    }
+
+    /// Returns the bytes which were attempted to convert.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Returns the bytes which were attempted to convert.
+    ///
+    /// Bytes on the heap are returned without copying.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self.bytes {
+            Bytes::Heap(v) => v,
+            bytes @ Bytes::Inline { .. } => bytes.as_slice().to_owned(),
+        }
+    }
+
+    /// Returns the details of the validation error.
+    #[inline]
+    pub fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
+
+impl fmt::Debug for SmallFromUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmallFromUtf8Error")
+            .field("bytes", &self.as_bytes())
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl PartialEq for SmallFromUtf8Error {
+    /// Compares the bytes and the validation errors, wherever the bytes are stored.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes() && self.error == other.error
+    }
+}
+
+impl Eq for SmallFromUtf8Error {}
+
+impl fmt::Display for SmallFromUtf8Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl error::Error for SmallFromUtf8Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<FromUtf8Error> for SmallFromUtf8Error {
+    #[inline]
+    fn from(e: FromUtf8Error) -> Self {
+        let error = e.utf8_error();
+        Self::from_vec(e.into_bytes(), error)
+    }
+}
+
+impl From<SmallFromUtf8Error> for FromUtf8Error {
+    /// Converts the error into `std`'s error.
+    ///
+    /// `FromUtf8Error` has no public constructor, so this validates the bytes again.
+    fn from(e: SmallFromUtf8Error) -> Self {
+        String::from_utf8(e.into_bytes())
+            .expect_err("Should never fail: the bytes are not valid UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::str;
+
+    use crate::string::SmallString;
+
+    /// Creates an error from the slice.
+    fn error_from_slice(bytes: &[u8]) -> SmallFromUtf8Error {
+        let error = str::from_utf8(bytes).unwrap_err();
+        SmallFromUtf8Error::from_slice(bytes, error)
+    }
+
+    #[test]
+    fn short_slice_is_stored_inline() {
+        let e = error_from_slice(b"ab\xFF");
+        assert!(matches!(e.bytes, Bytes::Inline { .. }));
+        assert_eq!(e.as_bytes(), b"ab\xFF");
+        assert_eq!(e.utf8_error().valid_up_to(), 2);
+        assert_eq!(e.into_bytes(), b"ab\xFF");
+    }
+
+    #[test]
+    fn long_slice_is_stored_on_heap() {
+        let mut bytes = vec![b'a'; INLINE_BYTES_CAPACITY + 1];
+        bytes.push(0xFF);
+        let e = error_from_slice(&bytes);
+        assert!(matches!(e.bytes, Bytes::Heap(_)));
+        let std_error: FromUtf8Error = e.into();
+        assert_eq!(std_error.as_bytes(), &bytes[..]);
+    }
+
+    #[test]
+    fn equality_ignores_storage() {
+        let heap = SmallString::from_utf8(b"ab\xFFcd".to_vec()).unwrap_err();
+        let inline = error_from_slice(b"ab\xFFcd");
+        assert!(matches!(heap.bytes, Bytes::Heap(_)));
+        assert!(matches!(inline.bytes, Bytes::Inline { .. }));
+        assert_eq!(heap, inline);
+        assert_ne!(heap, error_from_slice(b"ab\xFFce"));
+        assert_ne!(heap, error_from_slice(b"a\xFFcd"));
+    }
+}
@@ -0,0 +1,395 @@
+//! HTML and XML escaping.
+
+use std::{error, fmt};
+
+use crate::{cow::SmallCow, string::SmallString};
+
+/// Returns the escaped form of the given byte in HTML, if it should be escaped.
+#[inline]
+fn html_replacement(b: u8) -> Option<&'static str> {
+    match b {
+        b'&' => Some("&amp;"),
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        b'"' => Some("&quot;"),
+        b'\'' => Some("&#39;"),
+        _ => None,
+    }
+}
+
+/// Returns the escaped form of the given byte in XML attribute values, if it should be escaped.
+///
+/// Attribute values are assumed to be quoted by `"`.
+/// Whitespaces other than U+0020 are escaped since they would be normalized by XML processors.
+#[inline]
+fn xml_attr_replacement(b: u8) -> Option<&'static str> {
+    match b {
+        b'&' => Some("&amp;"),
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        b'"' => Some("&quot;"),
+        b'\t' => Some("&#x9;"),
+        b'\n' => Some("&#xA;"),
+        b'\r' => Some("&#xD;"),
+        _ => None,
+    }
+}
+
+/// Returns the escaped form of the given byte in XML text nodes, if it should be escaped.
+///
+/// `>` is escaped in order to prevent `]]>` from appearing in the output.
+#[inline]
+fn xml_text_replacement(b: u8) -> Option<&'static str> {
+    match b {
+        b'&' => Some("&amp;"),
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        _ => None,
+    }
+}
+
+/// Appends the escaped string to `dest`.
+///
+/// All bytes to be replaced are ASCII, so slicing at them never splits a character.
+fn push_escaped_with<F>(dest: &mut SmallString, s: &str, replacement: F)
+where
+    F: Fn(u8) -> Option<&'static str>,
+{
+    let mut rest = s;
+    while let Some((pos, rep)) = rest
+        .bytes()
+        .enumerate()
+        .find_map(|(pos, b)| replacement(b).map(|rep| (pos, rep)))
+    {
+        dest.push_str(&rest[..pos]);
+        dest.push_str(rep);
+        rest = &rest[(pos + 1)..];
+    }
+    dest.push_str(rest);
+}
+
+/// Escapes the given string using the given replacement table.
+fn escape_with<F>(s: &str, replacement: F) -> SmallCow<'_>
+where
+    F: Fn(u8) -> Option<&'static str>,
+{
+    let first = match s.bytes().position(|b| replacement(b).is_some()) {
+        Some(pos) => pos,
+        None => return SmallCow::Borrowed(s),
+    };
+
+    // Most strings will have only a few characters to be escaped.
+    let mut escaped = SmallString::with_capacity(s.len() + 8);
+    escaped.push_str(&s[..first]);
+    push_escaped_with(&mut escaped, &s[first..], replacement);
+
+    SmallCow::Owned(escaped)
+}
+
+/// Appends the given string to `dest`, escaping characters special in HTML.
+///
+/// `&`, `<`, `>`, `"`, and `'` are escaped.
+/// The result can be used both in text and in attribute values.
+#[inline]
+pub fn push_escaped_html(dest: &mut SmallString, s: &str) {
+    push_escaped_with(dest, s, html_replacement);
+}
+
+/// Appends the given string to `dest`, escaping characters special in XML attribute values.
+///
+/// The result is intended to be quoted by `"`.
+#[inline]
+pub fn push_escaped_xml_attr(dest: &mut SmallString, s: &str) {
+    push_escaped_with(dest, s, xml_attr_replacement);
+}
+
+/// Appends the given string to `dest`, escaping characters special in XML text nodes.
+#[inline]
+pub fn push_escaped_xml_text(dest: &mut SmallString, s: &str) {
+    push_escaped_with(dest, s, xml_text_replacement);
+}
+
+/// Escapes characters special in HTML.
+///
+/// Returns the borrowed string if nothing needs to be escaped.
+#[inline]
+pub fn escape_html(s: &str) -> SmallCow<'_> {
+    escape_with(s, html_replacement)
+}
+
+/// Escapes characters special in XML attribute values.
+///
+/// Returns the borrowed string if nothing needs to be escaped.
+#[inline]
+pub fn escape_xml_attr(s: &str) -> SmallCow<'_> {
+    escape_with(s, xml_attr_replacement)
+}
+
+/// Escapes characters special in XML text nodes.
+///
+/// Returns the borrowed string if nothing needs to be escaped.
+#[inline]
+pub fn escape_xml_text(s: &str) -> SmallCow<'_> {
+    escape_with(s, xml_text_replacement)
+}
+
+/// Error on unescaping a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnescapeError {
+    /// Byte offset of the `&` starting the invalid reference.
+    offset: usize,
+}
+
+impl UnescapeError {
+    /// Returns the byte offset of the `&` which starts the invalid reference.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for UnescapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid character reference at byte {}", self.offset)
+    }
+}
+
+impl error::Error for UnescapeError {}
+
+/// Names of the HTML entities for U+00A0 to U+00FF, in the order of the code points.
+const HTML_LATIN1_ENTITIES: [&str; 96] = [
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf",
+    "laquo", "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro",
+    "para", "middot", "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest",
+    "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute",
+    "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve", "Oacute",
+    "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute",
+    "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde",
+    "ograve", "oacute", "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc",
+    "uuml", "yacute", "thorn", "yuml",
+];
+
+/// Resolves the name of an entity predefined by XML.
+fn resolve_xml_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => None,
+    }
+}
+
+/// Resolves the name of an HTML entity.
+///
+/// The entities predefined by XML, Latin-1 characters, and common punctuations and
+/// symbols (the entities defined by HTML 4, except for Greek letters and mathematical
+/// symbols rarely used in documents) are supported.
+fn resolve_html_entity(name: &str) -> Option<char> {
+    if let Some(ch) = resolve_xml_entity(name) {
+        return Some(ch);
+    }
+    if let Some(i) = HTML_LATIN1_ENTITIES
+        .iter()
+        .position(|&entity| entity == name)
+    {
+        return std::char::from_u32(0xA0 + i as u32);
+    }
+    let ch = match name {
+        "OElig" => '\u{0152}',
+        "oelig" => '\u{0153}',
+        "Scaron" => '\u{0160}',
+        "scaron" => '\u{0161}',
+        "Yuml" => '\u{0178}',
+        "fnof" => '\u{0192}',
+        "circ" => '\u{02C6}',
+        "tilde" => '\u{02DC}',
+        "ensp" => '\u{2002}',
+        "emsp" => '\u{2003}',
+        "thinsp" => '\u{2009}',
+        "zwnj" => '\u{200C}',
+        "zwj" => '\u{200D}',
+        "lrm" => '\u{200E}',
+        "rlm" => '\u{200F}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "sbquo" => '\u{201A}',
+        "ldquo" => '\u{201C}',
+        "rdquo" => '\u{201D}',
+        "bdquo" => '\u{201E}',
+        "dagger" => '\u{2020}',
+        "Dagger" => '\u{2021}',
+        "bull" => '\u{2022}',
+        "hellip" => '\u{2026}',
+        "permil" => '\u{2030}',
+        "prime" => '\u{2032}',
+        "Prime" => '\u{2033}',
+        "lsaquo" => '\u{2039}',
+        "rsaquo" => '\u{203A}',
+        "oline" => '\u{203E}',
+        "euro" => '\u{20AC}',
+        "trade" => '\u{2122}',
+        "larr" => '\u{2190}',
+        "uarr" => '\u{2191}',
+        "rarr" => '\u{2192}',
+        "darr" => '\u{2193}',
+        "harr" => '\u{2194}',
+        "minus" => '\u{2212}',
+        "infin" => '\u{221E}',
+        "ne" => '\u{2260}',
+        "le" => '\u{2264}',
+        "ge" => '\u{2265}',
+        _ => return None,
+    };
+    Some(ch)
+}
+
+/// Resolves the body of a character or entity reference (without `&` and `;`).
+///
+/// Named entities are resolved by the given function.
+fn resolve_reference<F>(name: &str, resolve_entity: F) -> Option<char>
+where
+    F: Fn(&str) -> Option<char>,
+{
+    let code = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        // `from_str_radix` accepts a leading `+`.
+        if hex.starts_with('+') {
+            return None;
+        }
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(dec) = name.strip_prefix('#') {
+        if dec.starts_with('+') {
+            return None;
+        }
+        dec.parse::<u32>().ok()?
+    } else {
+        return resolve_entity(name);
+    };
+
+    match std::char::from_u32(code) {
+        Some('\0') => None,
+        ch => ch,
+    }
+}
+
+/// Appends the given string to `dest`, resolving references with the given entity resolver.
+fn push_unescaped_with<F>(
+    dest: &mut SmallString,
+    s: &str,
+    resolve_entity: F,
+) -> Result<(), UnescapeError>
+where
+    F: Fn(&str) -> Option<char>,
+{
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        let offset = s.len() - rest.len() + amp;
+        dest.push_str(&rest[..amp]);
+        let reference = &rest[(amp + 1)..];
+        let semicolon = reference.find(';').ok_or(UnescapeError { offset })?;
+        let ch = resolve_reference(&reference[..semicolon], &resolve_entity)
+            .ok_or(UnescapeError { offset })?;
+        dest.push(ch);
+        rest = &reference[(semicolon + 1)..];
+    }
+    dest.push_str(rest);
+
+    Ok(())
+}
+
+/// Resolves references with the given entity resolver.
+fn unescape_with<F>(s: &str, resolve_entity: F) -> Result<SmallCow<'_>, UnescapeError>
+where
+    F: Fn(&str) -> Option<char>,
+{
+    let first = match s.find('&') {
+        Some(pos) => pos,
+        None => return Ok(SmallCow::Borrowed(s)),
+    };
+
+    // Unescaped string is never longer than the source.
+    let mut unescaped = SmallString::with_capacity(s.len());
+    unescaped.push_str(&s[..first]);
+    push_unescaped_with(&mut unescaped, &s[first..], resolve_entity).map_err(|e| {
+        UnescapeError {
+            offset: e.offset + first,
+        }
+    })?;
+
+    Ok(SmallCow::Owned(unescaped))
+}
+
+/// Appends the given string to `dest`, resolving character and entity references.
+///
+/// The entities predefined by XML (`&amp;`, `&lt;`, `&gt;`, `&quot;`, and `&apos;`) and
+/// numeric character references (such as `&#39;` and `&#x27;`) are supported.
+/// Use [`push_unescaped_html`] for HTML named entities such as `&nbsp;`.
+///
+/// On error, `dest` contains the string unescaped up to the invalid reference.
+///
+/// [`push_unescaped_html`]: fn.push_unescaped_html.html
+#[inline]
+pub fn push_unescaped(dest: &mut SmallString, s: &str) -> Result<(), UnescapeError> {
+    push_unescaped_with(dest, s, resolve_xml_entity)
+}
+
+/// Appends the given string to `dest`, resolving HTML character and entity references.
+///
+/// In addition to the references supported by [`push_unescaped`], common HTML named
+/// entities such as `&nbsp;`, `&copy;`, `&eacute;`, and `&mdash;` are supported.
+/// References must be terminated by `;`.
+///
+/// On error, `dest` contains the string unescaped up to the invalid reference.
+///
+/// [`push_unescaped`]: fn.push_unescaped.html
+#[inline]
+pub fn push_unescaped_html(dest: &mut SmallString, s: &str) -> Result<(), UnescapeError> {
+    push_unescaped_with(dest, s, resolve_html_entity)
+}
+
+/// Resolves character and entity references.
+///
+/// Returns the borrowed string if it contains no references.
+/// See [`push_unescaped`] for supported references.
+///
+/// [`push_unescaped`]: fn.push_unescaped.html
+#[inline]
+pub fn unescape(s: &str) -> Result<SmallCow<'_>, UnescapeError> {
+    unescape_with(s, resolve_xml_entity)
+}
+
+/// Resolves HTML character and entity references.
+///
+/// Returns the borrowed string if it contains no references.
+/// See [`push_unescaped_html`] for supported references.
+///
+/// [`push_unescaped_html`]: fn.push_unescaped_html.html
+#[inline]
+pub fn unescape_html(s: &str) -> Result<SmallCow<'_>, UnescapeError> {
+    unescape_with(s, resolve_html_entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_html_entities() {
+        let s = "&copy; 2020&nbsp;A&amp;B &mdash; &eacute;&yuml;&#x27;";
+        assert_eq!(
+            unescape_html(s).unwrap().as_str(),
+            "\u{A9} 2020\u{A0}A&B \u{2014} \u{E9}\u{FF}'"
+        );
+        assert_eq!(unescape_html("a &unknown; b").unwrap_err().offset(), 2);
+    }
+
+    #[test]
+    fn unescape_xml_rejects_html_entities() {
+        assert_eq!(unescape("&lt;&apos;").unwrap().as_str(), "<'");
+        assert_eq!(unescape("a&nbsp;").unwrap_err().offset(), 1);
+    }
+}
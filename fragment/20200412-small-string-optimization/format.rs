@@ -0,0 +1,156 @@
+//! Formatting into `SmallString`.
+
+use std::fmt::{self, Write};
+
+use crate::string::SmallString;
+
+/// Conversion into a `SmallString` by formatting.
+///
+/// This is `ToString` for `SmallString`, and the value is formatted directly into
+/// the `SmallString` without an intermediate `String`.
+///
+/// For integers, [`SmallString::from_dec`] and [`SmallString::from_hex`] are faster
+/// since they do not use the `std::fmt` machinery.
+/// For floating point numbers, `Display` already produces the shortest representation
+/// which round-trips.
+///
+/// [`SmallString::from_dec`]: ../string/struct.SmallString.html#method.from_dec
+/// [`SmallString::from_hex`]: ../string/struct.SmallString.html#method.from_hex
+pub trait ToSmallString {
+    /// Converts the value into a `SmallString`.
+    fn to_small_string(&self) -> SmallString;
+}
+
+impl<T: fmt::Display + ?Sized> ToSmallString for T {
+    #[inline]
+    fn to_small_string(&self) -> SmallString {
+        let mut s = SmallString::new();
+        write!(s, "{}", self).expect("A Display implementation returned an error unexpectedly");
+        s
+    }
+}
+
+/// Maximum length of the formatted integer.
+///
+/// `i128::MIN` has 39 digits and a sign.
+const DIGITS_CAPACITY: usize = 40;
+
+/// Formatted integer.
+#[derive(Debug, Clone, Copy)]
+pub struct Digits {
+    /// Buffer.
+    buf: [u8; DIGITS_CAPACITY],
+    /// Start position of the formatted integer.
+    ///
+    /// The formatted integer is `buf[start..]`.
+    start: usize,
+}
+
+impl Digits {
+    /// Creates a new empty value.
+    #[inline]
+    fn new() -> Self {
+        Self {
+            buf: [0; DIGITS_CAPACITY],
+            start: DIGITS_CAPACITY,
+        }
+    }
+
+    /// Prepends the ASCII character.
+    #[inline]
+    fn push_front(&mut self, b: u8) {
+        debug_assert!(b.is_ascii());
+        self.start -= 1;
+        self.buf[self.start] = b;
+    }
+
+    /// Returns the formatted integer.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.buf[self.start..])
+            .expect("Should never fail: digits are ASCII characters")
+    }
+
+    /// Returns the sign (`"-"` or `""`) and the digits separately.
+    #[inline]
+    pub(crate) fn sign_and_digits(&self) -> (&str, &str) {
+        let s = self.as_str();
+        let sign_len = if s.starts_with('-') { 1 } else { 0 };
+        s.split_at(sign_len)
+    }
+}
+
+/// Sealed trait.
+mod private {
+    /// Sealed trait.
+    pub trait Sealed {}
+}
+
+/// Integer types which can be formatted without the `std::fmt` machinery.
+pub trait Integer: Copy + private::Sealed {
+    /// Formats the integer in decimal.
+    fn to_dec_digits(self) -> Digits;
+
+    /// Formats the integer in lower-case hexadecimal.
+    ///
+    /// As `{:x}` does, negative values are formatted as two's complement.
+    fn to_hex_digits(self) -> Digits;
+}
+
+/// Hexadecimal digits.
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+macro_rules! impl_integer {
+    ($($ty:ty => $unsigned:ty, $split_sign:expr;)*) => {
+        $(
+            impl private::Sealed for $ty {}
+
+            impl Integer for $ty {
+                fn to_dec_digits(self) -> Digits {
+                    let split_sign: fn($ty) -> (bool, $unsigned) = $split_sign;
+                    let (negative, mut n) = split_sign(self);
+                    let mut digits = Digits::new();
+                    loop {
+                        digits.push_front(b'0' + (n % 10) as u8);
+                        n /= 10;
+                        if n == 0 {
+                            break;
+                        }
+                    }
+                    if negative {
+                        digits.push_front(b'-');
+                    }
+                    digits
+                }
+
+                fn to_hex_digits(self) -> Digits {
+                    let mut n = self as $unsigned;
+                    let mut digits = Digits::new();
+                    loop {
+                        digits.push_front(HEX_DIGITS[(n & 0xf) as usize]);
+                        n >>= 4;
+                        if n == 0 {
+                            break;
+                        }
+                    }
+                    digits
+                }
+            }
+        )*
+    };
+}
+
+impl_integer! {
+    u8 => u8, |n| (false, n);
+    u16 => u16, |n| (false, n);
+    u32 => u32, |n| (false, n);
+    u64 => u64, |n| (false, n);
+    u128 => u128, |n| (false, n);
+    usize => usize, |n| (false, n);
+    i8 => u8, |n| (n < 0, n.unsigned_abs());
+    i16 => u16, |n| (n < 0, n.unsigned_abs());
+    i32 => u32, |n| (n < 0, n.unsigned_abs());
+    i64 => u64, |n| (n < 0, n.unsigned_abs());
+    i128 => u128, |n| (n < 0, n.unsigned_abs());
+    isize => usize, |n| (n < 0, n.unsigned_abs());
+}
@@ -0,0 +1,32 @@
+//! Fuzz target for `string.rs` with `inlinable_string` backend.
+//!
+//! This target requires `small-string` feature of the fuzz crate:
+//! `cargo fuzz run --features small-string inlinable_string`.
+
+#![no_main]
+// Only a part of the included modules is used by the fuzz target.
+#![allow(dead_code)]
+
+#[path = "../../cow.rs"]
+mod cow;
+#[path = "../../error.rs"]
+mod error;
+#[path = "../../format.rs"]
+mod format;
+#[path = "../../io.rs"]
+mod io;
+#[path = "../../map.rs"]
+mod map;
+#[path = "../ops.rs"]
+mod ops;
+#[path = "../../pattern.rs"]
+mod pattern;
+#[path = "../../slug.rs"]
+mod slug;
+#[path = "../../string.rs"]
+mod string;
+
+/// String type to be tested.
+type FuzzString = string::SmallString;
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| ops::run(data));
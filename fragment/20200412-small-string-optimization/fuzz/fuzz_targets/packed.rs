@@ -0,0 +1,141 @@
+//! Fuzz target for `packed.rs`.
+//!
+//! `PackedSmallString` has a smaller API than `String`, so this target runs
+//! its own subset of the operations in `ops.rs`.
+
+#![no_main]
+// Only a part of the included modules is used by the fuzz target.
+#![allow(dead_code)]
+
+#[path = "../../cow.rs"]
+mod cow;
+#[path = "../../error.rs"]
+mod error;
+#[path = "../../format.rs"]
+mod format;
+#[path = "../../io.rs"]
+mod io;
+#[path = "../../map.rs"]
+mod map;
+#[path = "../../packed.rs"]
+mod packed;
+#[path = "../../pattern.rs"]
+mod pattern;
+#[path = "../../slug.rs"]
+mod slug;
+#[path = "../../string2.rs"]
+mod string;
+
+use std::iter;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::packed::{is_packable, PackedSmallString, UTF8_CAPACITY};
+
+/// Returns an arbitrary char boundary position in the given string.
+fn char_boundary(u: &mut Unstructured<'_>, s: &str) -> Result<usize> {
+    let nth = u.choose_index(s.chars().count() + 1)?;
+    Ok(s.char_indices()
+        .map(|(pos, _)| pos)
+        .chain(iter::once(s.len()))
+        .nth(nth)
+        .expect("Should never fail: `nth` is in bounds"))
+}
+
+/// Returns an arbitrary string, biased towards packable characters.
+fn arbitrary_str<'a>(u: &mut Unstructured<'a>) -> Result<&'a str> {
+    let s = <&str>::arbitrary(u)?;
+    if bool::arbitrary(u)? {
+        let end = s
+            .find(|ch: char| !is_packable(ch.encode_utf8(&mut [0; 4])))
+            .unwrap_or(s.len());
+        return Ok(&s[..end]);
+    }
+    Ok(s)
+}
+
+/// Checks that the string has the expected content.
+fn check(packed: &PackedSmallString, oracle: &str) {
+    assert_eq!(&*packed.as_str(), oracle);
+    assert_eq!(packed.len(), oracle.len());
+    assert_eq!(packed.is_empty(), oracle.is_empty());
+    assert!(!packed.is_packed() || is_packable(oracle));
+    assert!(!packed.is_inline() || is_packable(oracle) || oracle.len() <= UTF8_CAPACITY);
+}
+
+/// Checks that the string is stored in the smallest representation.
+fn check_shrunk(packed: &PackedSmallString, oracle: &str) {
+    assert_eq!(packed.is_packed(), is_packable(oracle));
+    assert_eq!(
+        packed.is_inline(),
+        is_packable(oracle) || oracle.len() <= UTF8_CAPACITY
+    );
+}
+
+/// Runs operations.
+fn run_ops(u: &mut Unstructured<'_>) -> Result<()> {
+    let init = arbitrary_str(u)?;
+    let mut packed = PackedSmallString::from(init);
+    let mut oracle = String::from(init);
+    check_shrunk(&packed, &oracle);
+    let mut saved = Vec::new();
+
+    while !u.is_empty() {
+        match u.int_in_range(0_u8..=9)? {
+            0 => {
+                let s = arbitrary_str(u)?;
+                packed.push_str(s);
+                oracle.push_str(s);
+            }
+            1 => {
+                let ch = char::arbitrary(u)?;
+                packed.push(ch);
+                oracle.push(ch);
+            }
+            2 => assert_eq!(packed.pop(), oracle.pop()),
+            3 => {
+                let new_len = char_boundary(u, &oracle)?;
+                packed.truncate(new_len);
+                oracle.truncate(new_len);
+            }
+            4 => {
+                packed.shrink_to_fit();
+                oracle.shrink_to_fit();
+                check_shrunk(&packed, &oracle);
+            }
+            5 => {
+                packed = match u.int_in_range(0_u8..=2)? {
+                    0 => PackedSmallString::from(oracle.as_str()),
+                    1 => PackedSmallString::from(oracle.clone()),
+                    _ => PackedSmallString::from(string::SmallString::from(oracle.as_str())),
+                };
+                check_shrunk(&packed, &oracle);
+            }
+            6 => saved.push((packed.clone(), oracle.clone())),
+            7 => {
+                if let Some((saved, saved_oracle)) = saved.pop() {
+                    assert_eq!(saved.clone().into_string(), saved_oracle);
+                    assert_eq!(saved.into_small_string().as_str(), saved_oracle);
+                }
+            }
+            8 => {
+                for (saved, saved_oracle) in &saved {
+                    assert_eq!(saved.cmp(&packed), saved_oracle.cmp(&oracle));
+                    assert_eq!(*saved == packed, *saved_oracle == oracle);
+                }
+            }
+            _ => {
+                packed.clear();
+                oracle.clear();
+            }
+        }
+        check(&packed, &oracle);
+    }
+
+    Ok(())
+}
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+    // Running out of data is not an error.
+    let _ = run_ops(&mut Unstructured::new(data));
+});
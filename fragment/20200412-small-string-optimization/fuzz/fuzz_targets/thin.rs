@@ -0,0 +1,31 @@
+//! Fuzz target for `thin.rs`.
+
+#![no_main]
+// Only a part of the included modules is used by the fuzz target.
+#![allow(dead_code)]
+
+#[path = "../../cow.rs"]
+mod cow;
+#[path = "../../error.rs"]
+mod error;
+#[path = "../../format.rs"]
+mod format;
+#[path = "../../io.rs"]
+mod io;
+#[path = "../../map.rs"]
+mod map;
+#[path = "../ops.rs"]
+mod ops;
+#[path = "../../pattern.rs"]
+mod pattern;
+#[path = "../../slug.rs"]
+mod slug;
+#[path = "../../string2.rs"]
+mod string;
+#[path = "../../thin.rs"]
+mod thin;
+
+/// String type to be tested.
+type FuzzString = thin::ThinSmallString;
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| ops::run(data));
@@ -0,0 +1,150 @@
+//! Operations on small strings, checked against `String`.
+//!
+//! This module is shared by fuzz targets: `crate::FuzzString` is the string
+//! type to be tested.
+
+use std::iter;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::FuzzString as SmallString;
+
+/// Returns an arbitrary char boundary position in the given string.
+fn char_boundary(u: &mut Unstructured<'_>, s: &str) -> Result<usize> {
+    let nth = u.choose_index(s.chars().count() + 1)?;
+    Ok(s.char_indices()
+        .map(|(pos, _)| pos)
+        .chain(iter::once(s.len()))
+        .nth(nth)
+        .expect("Should never fail: `nth` is in bounds"))
+}
+
+/// Returns an arbitrary range in the given string, with both ends on char boundaries.
+fn char_range(u: &mut Unstructured<'_>, s: &str) -> Result<(usize, usize)> {
+    let a = char_boundary(u, s)?;
+    let b = char_boundary(u, s)?;
+    Ok((a.min(b), a.max(b)))
+}
+
+/// Checks that the string has the expected content.
+#[inline]
+fn check(small: &SmallString, oracle: &str) {
+    assert_eq!(small.as_str(), oracle);
+    assert_eq!(small.len(), oracle.len());
+    assert_eq!(small.is_empty(), oracle.is_empty());
+    assert!(small.capacity() >= small.len());
+}
+
+/// Interprets the given bytes as operations, and checks the string type behaves as `String`.
+pub fn run(data: &[u8]) {
+    // Running out of data is not an error.
+    let _ = run_ops(&mut Unstructured::new(data));
+}
+
+/// Runs operations.
+fn run_ops(u: &mut Unstructured<'_>) -> Result<()> {
+    let init = <&str>::arbitrary(u)?;
+    let mut small = SmallString::from(init);
+    let mut oracle = String::from(init);
+    check(&small, &oracle);
+
+    while !u.is_empty() {
+        match u.int_in_range(0_u8..=14)? {
+            0 => {
+                let s = <&str>::arbitrary(u)?;
+                small.push_str(s);
+                oracle.push_str(s);
+            }
+            1 => {
+                let ch = char::arbitrary(u)?;
+                small.push(ch);
+                oracle.push(ch);
+            }
+            2 => {
+                let idx = char_boundary(u, &oracle)?;
+                let s = <&str>::arbitrary(u)?;
+                small.insert_str(idx, s);
+                oracle.insert_str(idx, s);
+            }
+            3 => {
+                let idx = char_boundary(u, &oracle)?;
+                let ch = char::arbitrary(u)?;
+                small.insert(idx, ch);
+                oracle.insert(idx, ch);
+            }
+            4 => {
+                let at = char_boundary(u, &oracle)?;
+                let small_tail = small.split_off(at);
+                let oracle_tail = oracle.split_off(at);
+                check(&small_tail, &oracle_tail);
+            }
+            5 => {
+                let (start, end) = char_range(u, &oracle)?;
+                let s = <&str>::arbitrary(u)?;
+                small.replace_range(start..end, s);
+                oracle.replace_range(start..end, s);
+            }
+            6 => {
+                let modulo = u32::from(u.int_in_range(1_u8..=4)?);
+                let pred = |ch: char| u32::from(ch) % modulo != 0;
+                small.retain(pred);
+                oracle.retain(pred);
+            }
+            7 => {
+                let (start, end) = char_range(u, &oracle)?;
+                let from_back = bool::arbitrary(u)?;
+                let (small_drained, oracle_drained): (String, String) = if from_back {
+                    (
+                        small.drain(start..end).rev().collect(),
+                        oracle.drain(start..end).rev().collect(),
+                    )
+                } else {
+                    (
+                        small.drain(start..end).collect(),
+                        oracle.drain(start..end).collect(),
+                    )
+                };
+                assert_eq!(small_drained, oracle_drained);
+            }
+            8 => {
+                let (start, end) = char_range(u, &oracle)?;
+                // Drop without consuming.
+                drop(small.drain(start..end));
+                drop(oracle.drain(start..end));
+            }
+            9 => {
+                small.shrink_to_fit();
+                oracle.shrink_to_fit();
+            }
+            10 => {
+                let new_len = char_boundary(u, &oracle)?;
+                small.truncate(new_len);
+                oracle.truncate(new_len);
+            }
+            11 => assert_eq!(small.pop(), oracle.pop()),
+            12 => {
+                if !oracle.is_empty() {
+                    let nth = u.choose_index(oracle.chars().count())?;
+                    let (idx, _) = oracle
+                        .char_indices()
+                        .nth(nth)
+                        .expect("Should never fail: `nth` is in bounds");
+                    assert_eq!(small.remove(idx), oracle.remove(idx));
+                }
+            }
+            13 => {
+                let additional = u.int_in_range(0..=64)?;
+                small.reserve(additional);
+                oracle.reserve(additional);
+                assert!(small.capacity() >= small.len() + additional);
+            }
+            _ => {
+                small.clear();
+                oracle.clear();
+            }
+        }
+        check(&small, &oracle);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,426 @@
+//! String with cached hash value.
+//!
+//! `HashedSmallString` is intended to be used as keys of large hash maps, such as
+//! keys of JSON objects.
+//! The hash value is computed once on creation, and the map only needs to read it
+//! on resizing and lookups.
+//! Maps should use `BuildPrehashedHasher`, which passes the cached value through.
+
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+};
+
+use crate::string::SmallString;
+
+/// Hasher builder used to compute hash values on creation by default.
+///
+/// This is SipHash with fixed keys: the hash values are stable in a process,
+/// but they are predictable.
+/// Use `with_hasher` constructors with a randomly seeded hasher builder for
+/// strings from untrusted sources.
+type DefaultBuildHasher = BuildHasherDefault<DefaultHasher>;
+
+/// Sealed trait.
+mod private {
+    /// Sealed trait.
+    pub trait Sealed {}
+}
+
+/// String with a precomputed hash value, used as a key of hash maps.
+///
+/// Maps should have [`HashedSmallString`] as the key type, and look up values with
+/// `&dyn HashedKey` obtained from [`HashedStr::as_key`] (or using [`HashedMapExt`]).
+///
+/// [`HashedSmallString`]: struct.HashedSmallString.html
+/// [`HashedStr::as_key`]: struct.HashedStr.html#method.as_key
+/// [`HashedMapExt`]: trait.HashedMapExt.html
+pub trait HashedKey: private::Sealed {
+    /// Returns the cached hash value.
+    fn hash_value(&self) -> u64;
+
+    /// Returns the string.
+    fn key_str(&self) -> &str;
+}
+
+impl Hash for dyn HashedKey + '_ {
+    /// Writes the cached hash value.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash_value());
+    }
+}
+
+impl PartialEq for dyn HashedKey + '_ {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.hash_value() == other.hash_value() && self.key_str() == other.key_str()
+    }
+}
+
+impl Eq for dyn HashedKey + '_ {}
+
+/// `SmallString` with a precomputed hash value.
+///
+/// `Hash` writes only the cached value, so this should be used with
+/// [`BuildPrehashedHasher`].
+///
+/// [`BuildPrehashedHasher`]: struct.BuildPrehashedHasher.html
+#[derive(Clone)]
+pub struct HashedSmallString {
+    /// Hash value.
+    hash: u64,
+    /// String.
+    string: SmallString,
+}
+
+impl HashedSmallString {
+    /// Creates a new value, computing the hash value with the default hasher.
+    #[inline]
+    pub fn new(string: SmallString) -> Self {
+        Self::with_hasher(&DefaultBuildHasher::default(), string)
+    }
+
+    /// Creates a new value, computing the hash value with the given hasher builder.
+    ///
+    /// Values created with different hasher builders should not be mixed in a map.
+    #[inline]
+    pub fn with_hasher<S: BuildHasher>(build_hasher: &S, string: SmallString) -> Self {
+        Self {
+            hash: build_hasher.hash_one(string.as_str()),
+            string,
+        }
+    }
+
+    /// Returns the cached hash value.
+    #[inline]
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.string.as_str()
+    }
+
+    /// Returns the string.
+    #[inline]
+    pub fn as_small_string(&self) -> &SmallString {
+        &self.string
+    }
+
+    /// Returns the string, discarding the hash value.
+    #[inline]
+    pub fn into_small_string(self) -> SmallString {
+        self.string
+    }
+
+    /// Returns the lookup key.
+    #[inline]
+    pub fn as_key(&self) -> &(dyn HashedKey + '_) {
+        self
+    }
+}
+
+impl private::Sealed for HashedSmallString {}
+
+impl HashedKey for HashedSmallString {
+    #[inline]
+    fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn key_str(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> Borrow<dyn HashedKey + 'a> for HashedSmallString {
+    #[inline]
+    fn borrow(&self) -> &(dyn HashedKey + 'a) {
+        self
+    }
+}
+
+impl Hash for HashedSmallString {
+    /// Writes the cached hash value.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_key().hash(state)
+    }
+}
+
+impl PartialEq for HashedSmallString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_key() == other.as_key()
+    }
+}
+
+impl Eq for HashedSmallString {}
+
+impl fmt::Debug for HashedSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.string, f)
+    }
+}
+
+impl fmt::Display for HashedSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.string, f)
+    }
+}
+
+impl AsRef<str> for HashedSmallString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<SmallString> for HashedSmallString {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<&str> for HashedSmallString {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self::new(s.into())
+    }
+}
+
+impl From<HashedSmallString> for SmallString {
+    #[inline]
+    fn from(s: HashedSmallString) -> Self {
+        s.into_small_string()
+    }
+}
+
+/// Borrowed string with a precomputed hash value, used to look up maps.
+#[derive(Debug, Clone, Copy)]
+pub struct HashedStr<'a> {
+    /// Hash value.
+    hash: u64,
+    /// String.
+    string: &'a str,
+}
+
+impl<'a> HashedStr<'a> {
+    /// Creates a new value, computing the hash value with the default hasher.
+    #[inline]
+    pub fn new(string: &'a str) -> Self {
+        Self::with_hasher(&DefaultBuildHasher::default(), string)
+    }
+
+    /// Creates a new value, computing the hash value with the given hasher builder.
+    #[inline]
+    pub fn with_hasher<S: BuildHasher>(build_hasher: &S, string: &'a str) -> Self {
+        Self {
+            hash: build_hasher.hash_one(string),
+            string,
+        }
+    }
+
+    /// Returns the cached hash value.
+    #[inline]
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the string.
+    #[inline]
+    pub fn as_str(&self) -> &'a str {
+        self.string
+    }
+
+    /// Returns the lookup key.
+    #[inline]
+    pub fn as_key(&self) -> &(dyn HashedKey + 'a) {
+        self
+    }
+}
+
+impl private::Sealed for HashedStr<'_> {}
+
+impl HashedKey for HashedStr<'_> {
+    #[inline]
+    fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn key_str(&self) -> &str {
+        self.string
+    }
+}
+
+/// Hasher which passes through the precomputed hash value.
+///
+/// Only `write_u64` is expected to be called. Other writes are mixed in
+/// naively, in order not to break maps with unexpected keys.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct PrehashedHasher {
+    /// Hash value.
+    hash: u64,
+}
+
+impl Hasher for PrehashedHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.hash = self.hash.rotate_left(8) ^ u64::from(b);
+        }
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.hash = i;
+    }
+}
+
+/// Hasher builder for maps keyed by `HashedSmallString`.
+pub type BuildPrehashedHasher = BuildHasherDefault<PrehashedHasher>;
+
+/// Extension trait for looking up maps keyed by `HashedSmallString` with `&str`.
+///
+/// The hash value of the key is computed with the default hasher.
+/// For keys created with other hashers, use `HashedStr::with_hasher` and `as_key`.
+pub trait HashedMapExt<V> {
+    /// Returns a reference to the value corresponding to the key.
+    fn get_str(&self, key: &str) -> Option<&V>;
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    fn get_str_mut(&mut self, key: &str) -> Option<&mut V>;
+
+    /// Returns `true` if the map contains the key.
+    fn contains_str(&self, key: &str) -> bool;
+
+    /// Removes the key from the map, and returns the value.
+    fn remove_str(&mut self, key: &str) -> Option<V>;
+}
+
+impl<V, S: BuildHasher> HashedMapExt<V> for HashMap<HashedSmallString, V, S> {
+    #[inline]
+    fn get_str(&self, key: &str) -> Option<&V> {
+        self.get(HashedStr::new(key).as_key())
+    }
+
+    #[inline]
+    fn get_str_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_mut(HashedStr::new(key).as_key())
+    }
+
+    #[inline]
+    fn contains_str(&self, key: &str) -> bool {
+        self.contains_key(HashedStr::new(key).as_key())
+    }
+
+    #[inline]
+    fn remove_str(&mut self, key: &str) -> Option<V> {
+        self.remove(HashedStr::new(key).as_key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::hash_map::RandomState;
+
+    /// Keys to insert, including ones longer than the inline capacity.
+    const KEYS: &[&str] = &[
+        "",
+        "id",
+        "class",
+        "a key which is long enough to be stored on the heap",
+        "\u{3042}\u{3044}\u{3046}",
+    ];
+
+    #[test]
+    fn hash_values_match() {
+        let random = RandomState::new();
+        for &key in KEYS {
+            let owned = HashedSmallString::from(key);
+            assert_eq!(owned.hash_value(), HashedStr::new(key).hash_value());
+            assert!(owned.as_key() == HashedStr::new(key).as_key());
+
+            let owned = HashedSmallString::with_hasher(&random, key.into());
+            let borrowed = HashedStr::with_hasher(&random, key);
+            assert_eq!(owned.hash_value(), borrowed.hash_value());
+            assert!(owned.as_key() == borrowed.as_key());
+        }
+    }
+
+    #[test]
+    fn prehashed_hasher_passes_hash_value_through() {
+        let build_hasher = BuildPrehashedHasher::default();
+        for &key in KEYS {
+            let owned = HashedSmallString::from(key);
+            assert_eq!(build_hasher.hash_one(&owned), owned.hash_value());
+            assert_eq!(
+                build_hasher.hash_one(HashedStr::new(key).as_key()),
+                owned.hash_value()
+            );
+        }
+    }
+
+    #[test]
+    fn look_up_with_str() {
+        let mut map = HashMap::with_hasher(BuildPrehashedHasher::default());
+        for (i, &key) in KEYS.iter().enumerate() {
+            assert!(map.insert(HashedSmallString::from(key), i).is_none());
+        }
+        for (i, &key) in KEYS.iter().enumerate() {
+            assert_eq!(map.get_str(key), Some(&i));
+            assert!(map.contains_str(key));
+            assert_eq!(map.get(HashedStr::new(key).as_key()), Some(&i));
+        }
+        assert_eq!(map.get_str("missing"), None);
+
+        *map.get_str_mut("id").unwrap() = 42;
+        assert_eq!(map.get_str("id"), Some(&42));
+        assert_eq!(map.remove_str("id"), Some(42));
+        assert_eq!(map.remove_str("id"), None);
+        assert!(!map.contains_str("id"));
+        assert_eq!(map.len(), KEYS.len() - 1);
+    }
+
+    #[test]
+    fn look_up_with_non_default_hasher() {
+        let random = RandomState::new();
+        // The map hasher is also different from the default.
+        let mut map = HashMap::new();
+        for (i, &key) in KEYS.iter().enumerate() {
+            map.insert(HashedSmallString::with_hasher(&random, key.into()), i);
+        }
+        for (i, &key) in KEYS.iter().enumerate() {
+            let lookup = HashedStr::with_hasher(&random, key);
+            assert_eq!(map.get(lookup.as_key()), Some(&i));
+        }
+        assert_eq!(
+            map.get(HashedStr::with_hasher(&random, "missing").as_key()),
+            None
+        );
+
+        // Keys created with the default hasher can be looked up with `&str`
+        // whatever the map hasher is.
+        let mut map = HashMap::new();
+        map.insert(HashedSmallString::from("class"), 1);
+        assert_eq!(map.get_str("class"), Some(&1));
+        assert_eq!(map.remove_str("class"), Some(1));
+        assert!(map.is_empty());
+    }
+}
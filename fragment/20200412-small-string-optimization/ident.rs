@@ -0,0 +1,712 @@
+//! Validated identifiers.
+//!
+//! Each type is a `SmallString` which is guaranteed to be valid in its grammar.
+//! Constructors validate the string and return an error with the offending byte
+//! offset, and `sanitize` constructors fix invalid strings instead.
+
+use std::{borrow::Borrow, convert::TryFrom, error, fmt, ops, str::FromStr};
+
+use crate::string::{IntoSmallString, SmallString};
+
+/// Replacement of invalid characters on sanitization.
+const REPLACEMENT: char = '_';
+
+/// Kind of identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdentKind {
+    /// XML `NCName` (name without colons).
+    XmlNcName,
+    /// XML `QName` (name with an optional prefix).
+    XmlQName,
+    /// HTML `id` attribute value.
+    HtmlId,
+    /// CSS identifier, possibly with escapes.
+    CssIdent,
+}
+
+impl fmt::Display for IdentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IdentKind::XmlNcName => "XML NCName",
+            IdentKind::XmlQName => "XML QName",
+            IdentKind::HtmlId => "HTML id",
+            IdentKind::CssIdent => "CSS identifier",
+        })
+    }
+}
+
+/// Reason of validation error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Reason {
+    /// The string or a part of it ended unexpectedly.
+    UnexpectedEnd,
+    /// The character is not allowed at the start.
+    InvalidStart(char),
+    /// The character is not allowed.
+    InvalidChar(char),
+    /// The escape sequence is invalid.
+    InvalidEscape,
+}
+
+/// Error on validating an identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidIdentError {
+    /// Kind of the identifier.
+    kind: IdentKind,
+    /// Byte offset of the error.
+    offset: usize,
+    /// Reason.
+    reason: Reason,
+}
+
+impl InvalidIdentError {
+    /// Creates a new error.
+    #[inline]
+    fn new(kind: IdentKind, offset: usize, reason: Reason) -> Self {
+        Self {
+            kind,
+            offset,
+            reason,
+        }
+    }
+
+    /// Returns the kind of the identifier.
+    #[inline]
+    pub fn kind(&self) -> IdentKind {
+        self.kind
+    }
+
+    /// Returns the byte offset of the offending character.
+    ///
+    /// If the string ended unexpectedly, this is the length of the string or the part.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for InvalidIdentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: ", self.kind)?;
+        match self.reason {
+            Reason::UnexpectedEnd => write!(f, "unexpected end at byte {}", self.offset),
+            Reason::InvalidStart(c) => write!(
+                f,
+                "character {:?} at byte {} cannot start the identifier",
+                c, self.offset
+            ),
+            Reason::InvalidChar(c) => write!(
+                f,
+                "character {:?} at byte {} is not allowed",
+                c, self.offset
+            ),
+            Reason::InvalidEscape => write!(f, "invalid escape at byte {}", self.offset),
+        }
+    }
+}
+
+impl error::Error for InvalidIdentError {}
+
+/// Returns `true` if the character can start an XML `NCName`.
+fn is_xml_name_start_char(c: char) -> bool {
+    matches!(
+        c,
+        'A'..='Z'
+            | '_'
+            | 'a'..='z'
+            | '\u{C0}'..='\u{D6}'
+            | '\u{D8}'..='\u{F6}'
+            | '\u{F8}'..='\u{2FF}'
+            | '\u{370}'..='\u{37D}'
+            | '\u{37F}'..='\u{1FFF}'
+            | '\u{200C}'..='\u{200D}'
+            | '\u{2070}'..='\u{218F}'
+            | '\u{2C00}'..='\u{2FEF}'
+            | '\u{3001}'..='\u{D7FF}'
+            | '\u{F900}'..='\u{FDCF}'
+            | '\u{FDF0}'..='\u{FFFD}'
+            | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+/// Returns `true` if the character can appear in an XML `NCName`.
+fn is_xml_name_char(c: char) -> bool {
+    match c {
+        '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}' => true,
+        c => is_xml_name_start_char(c),
+    }
+}
+
+/// Validates the string as XML `NCName`.
+///
+/// `base` is the byte offset of `s` in the whole string.
+pub(crate) fn validate_xml_ncname(
+    s: &str,
+    kind: IdentKind,
+    base: usize,
+) -> Result<(), InvalidIdentError> {
+    let mut chars = s.char_indices();
+    match chars.next() {
+        None => return Err(InvalidIdentError::new(kind, base, Reason::UnexpectedEnd)),
+        Some((_, c)) if is_xml_name_start_char(c) => {}
+        Some((_, c)) => return Err(InvalidIdentError::new(kind, base, Reason::InvalidStart(c))),
+    }
+    match chars.find(|&(_, c)| !is_xml_name_char(c)) {
+        Some((pos, c)) => Err(InvalidIdentError::new(
+            kind,
+            base + pos,
+            Reason::InvalidChar(c),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Validates the string as XML `QName`.
+pub(crate) fn validate_xml_qname(s: &str) -> Result<(), InvalidIdentError> {
+    let kind = IdentKind::XmlQName;
+    match s.find(':') {
+        Some(0) => Err(InvalidIdentError::new(kind, 0, Reason::InvalidStart(':'))),
+        Some(colon) => {
+            validate_xml_ncname(&s[..colon], kind, 0)?;
+            validate_xml_ncname(&s[(colon + 1)..], kind, colon + 1)
+        }
+        None => validate_xml_ncname(s, kind, 0),
+    }
+}
+
+/// Returns `true` if the character is an HTML whitespace.
+#[inline]
+fn is_html_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ')
+}
+
+/// Validates the string as HTML `id`.
+fn validate_html_id(s: &str) -> Result<(), InvalidIdentError> {
+    let kind = IdentKind::HtmlId;
+    if s.is_empty() {
+        return Err(InvalidIdentError::new(kind, 0, Reason::UnexpectedEnd));
+    }
+    match s.char_indices().find(|&(_, c)| is_html_whitespace(c)) {
+        Some((pos, c)) => Err(InvalidIdentError::new(kind, pos, Reason::InvalidChar(c))),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if the character can start a CSS identifier without escape.
+#[inline]
+fn is_css_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+/// Returns `true` if the character can appear in a CSS identifier without escape.
+#[inline]
+fn is_css_ident_char(c: char) -> bool {
+    is_css_ident_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+/// Unit of CSS identifier.
+#[derive(Debug, Clone, Copy)]
+enum CssUnit {
+    /// Raw character.
+    Char(char),
+    /// Escape sequence.
+    Escape,
+}
+
+/// Reads a character or an escape sequence of CSS at the given position.
+///
+/// Returns the unit and the position next to it.
+fn read_css_unit(s: &str, pos: usize) -> Result<Option<(CssUnit, usize)>, InvalidIdentError> {
+    let invalid_escape = || InvalidIdentError::new(IdentKind::CssIdent, pos, Reason::InvalidEscape);
+    match s[pos..].chars().next() {
+        None => return Ok(None),
+        Some('\\') => {}
+        Some('\0') => {
+            return Err(InvalidIdentError::new(
+                IdentKind::CssIdent,
+                pos,
+                Reason::InvalidChar('\0'),
+            ))
+        }
+        Some(c) => return Ok(Some((CssUnit::Char(c), pos + c.len_utf8()))),
+    }
+
+    let escaped = s[(pos + 1)..].chars().next().ok_or_else(invalid_escape)?;
+    if matches!(escaped, '\n' | '\r' | '\x0C' | '\0') {
+        return Err(invalid_escape());
+    }
+    if !escaped.is_ascii_hexdigit() {
+        return Ok(Some((CssUnit::Escape, pos + 1 + escaped.len_utf8())));
+    }
+
+    // Up to 6 hex digits, followed by an optional whitespace.
+    let digits_len = s[(pos + 1)..]
+        .bytes()
+        .take(6)
+        .take_while(u8::is_ascii_hexdigit)
+        .count();
+    let mut next = pos + 1 + digits_len;
+    let rest = &s[next..];
+    if rest.starts_with("\r\n") {
+        next += 2;
+    } else if rest.starts_with(&[' ', '\t', '\n', '\r', '\x0C'][..]) {
+        next += 1;
+    }
+    Ok(Some((CssUnit::Escape, next)))
+}
+
+/// Validates the string as CSS identifier.
+///
+/// The string is regarded as written in a stylesheet, so escape sequences are allowed.
+fn validate_css_ident(s: &str) -> Result<(), InvalidIdentError> {
+    let kind = IdentKind::CssIdent;
+    let unexpected_end = |pos| InvalidIdentError::new(kind, pos, Reason::UnexpectedEnd);
+
+    let (first, mut pos) = read_css_unit(s, 0)?.ok_or_else(|| unexpected_end(0))?;
+    match first {
+        CssUnit::Char('-') => {
+            let second_pos = pos;
+            let (second, next) = read_css_unit(s, pos)?.ok_or_else(|| unexpected_end(pos))?;
+            match second {
+                CssUnit::Char(c) if c != '-' && !is_css_ident_start(c) => {
+                    return Err(InvalidIdentError::new(
+                        kind,
+                        second_pos,
+                        Reason::InvalidStart(c),
+                    ))
+                }
+                _ => {}
+            }
+            pos = next;
+        }
+        CssUnit::Char(c) if !is_css_ident_start(c) => {
+            return Err(InvalidIdentError::new(kind, 0, Reason::InvalidStart(c)))
+        }
+        _ => {}
+    }
+
+    while let Some((unit, next)) = read_css_unit(s, pos)? {
+        if let CssUnit::Char(c) = unit {
+            if !is_css_ident_char(c) {
+                return Err(InvalidIdentError::new(kind, pos, Reason::InvalidChar(c)));
+            }
+        }
+        pos = next;
+    }
+
+    Ok(())
+}
+
+/// Defines a newtype of `SmallString` and common trait impls.
+macro_rules! define_ident {
+    ($(#[$meta:meta])* $ty:ident, $validate:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $ty {
+            /// Inner string.
+            inner: SmallString,
+        }
+
+        impl $ty {
+            /// Returns the string.
+            #[inline]
+            pub fn as_str(&self) -> &str {
+                self.inner.as_str()
+            }
+
+            /// Returns the inner string.
+            #[inline]
+            pub fn into_small_string(self) -> SmallString {
+                self.inner
+            }
+        }
+
+        impl fmt::Display for $ty {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ops::Deref for $ty {
+            type Target = str;
+
+            #[inline]
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl AsRef<str> for $ty {
+            #[inline]
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl Borrow<str> for $ty {
+            #[inline]
+            fn borrow(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl From<$ty> for SmallString {
+            #[inline]
+            fn from(v: $ty) -> Self {
+                v.into_small_string()
+            }
+        }
+
+        impl From<$ty> for String {
+            #[inline]
+            fn from(v: $ty) -> Self {
+                v.as_str().to_owned()
+            }
+        }
+
+        impl TryFrom<&str> for $ty {
+            type Error = InvalidIdentError;
+
+            #[inline]
+            fn try_from(s: &str) -> Result<Self, Self::Error> {
+                let validate: fn(&str) -> Result<(), InvalidIdentError> = $validate;
+                validate(s)?;
+                Ok(Self { inner: s.into() })
+            }
+        }
+
+        impl FromStr for $ty {
+            type Err = InvalidIdentError;
+
+            #[inline]
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::try_from(s)
+            }
+        }
+
+        impl PartialEq<str> for $ty {
+            #[inline]
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $ty {
+            #[inline]
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+    };
+}
+
+define_ident! {
+    /// XML name, either `NCName` or `QName`.
+    XmlName, validate_xml_qname
+}
+
+impl XmlName {
+    /// Creates a new name, validating it as `QName`.
+    #[inline]
+    pub fn new(s: impl IntoSmallString) -> Result<Self, InvalidIdentError> {
+        let inner = s.into_small_string();
+        validate_xml_qname(&inner)?;
+        Ok(Self { inner })
+    }
+
+    /// Creates a new name, validating it as `NCName`.
+    #[inline]
+    pub fn new_ncname(s: impl IntoSmallString) -> Result<Self, InvalidIdentError> {
+        let inner = s.into_small_string();
+        validate_xml_ncname(&inner, IdentKind::XmlNcName, 0)?;
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `NCName`, replacing invalid characters with `_`.
+    ///
+    /// Colons are also replaced. If the string starts with a character which
+    /// can only appear after the start, `_` is prepended.
+    /// An empty string results in `_`.
+    pub fn sanitize(s: &str) -> Self {
+        if validate_xml_ncname(s, IdentKind::XmlNcName, 0).is_ok() {
+            return Self { inner: s.into() };
+        }
+
+        let mut inner = SmallString::with_capacity(s.len() + 1);
+        match s.chars().next() {
+            Some(c) if is_xml_name_start_char(c) => {}
+            Some(c) if is_xml_name_char(c) => inner.push(REPLACEMENT),
+            _ => {}
+        }
+        inner.extend(
+            s.chars()
+                .map(|c| if is_xml_name_char(c) { c } else { REPLACEMENT }),
+        );
+        if inner.is_empty() {
+            inner.push(REPLACEMENT);
+        }
+        Self { inner }
+    }
+
+    /// Returns `true` if the name has no prefix, i.e. the name is a `NCName`.
+    #[inline]
+    pub fn is_ncname(&self) -> bool {
+        !self.inner.contains(':')
+    }
+}
+
+define_ident! {
+    /// HTML `id` attribute value.
+    ///
+    /// This is a non-empty string without HTML whitespaces.
+    HtmlId, validate_html_id
+}
+
+impl HtmlId {
+    /// Creates a new id, validating it.
+    #[inline]
+    pub fn new(s: impl IntoSmallString) -> Result<Self, InvalidIdentError> {
+        let inner = s.into_small_string();
+        validate_html_id(&inner)?;
+        Ok(Self { inner })
+    }
+
+    /// Creates a new id, replacing whitespaces with `_`.
+    ///
+    /// An empty string results in `_`.
+    pub fn sanitize(s: &str) -> Self {
+        if s.is_empty() {
+            return Self {
+                inner: REPLACEMENT.into_small_string(),
+            };
+        }
+        let inner = s
+            .chars()
+            .map(|c| {
+                if is_html_whitespace(c) {
+                    REPLACEMENT
+                } else {
+                    c
+                }
+            })
+            .collect();
+        Self { inner }
+    }
+}
+
+define_ident! {
+    /// CSS identifier, as written in a stylesheet.
+    ///
+    /// The identifier may contain escape sequences, and they are kept as is.
+    CssIdent, validate_css_ident
+}
+
+impl CssIdent {
+    /// Creates a new identifier, validating it.
+    #[inline]
+    pub fn new(s: impl IntoSmallString) -> Result<Self, InvalidIdentError> {
+        let inner = s.into_small_string();
+        validate_css_ident(&inner)?;
+        Ok(Self { inner })
+    }
+
+    /// Creates a new identifier which represents the given string, escaping
+    /// characters as necessary.
+    ///
+    /// This follows "serialize an identifier" algorithm of CSSOM, except that
+    /// an empty string results in `_`.
+    /// NUL characters are replaced with U+FFFD as CSS parsers do.
+    pub fn sanitize(s: &str) -> Self {
+        if s.is_empty() {
+            return Self {
+                inner: REPLACEMENT.into_small_string(),
+            };
+        }
+
+        let mut inner = SmallString::with_capacity(s.len());
+        let starts_with_hyphen = s.starts_with('-');
+        for (i, c) in s.chars().enumerate() {
+            let escape_as_code_point = matches!(c, '\x01'..='\x1F' | '\x7F')
+                || (c.is_ascii_digit() && (i == 0 || (i == 1 && starts_with_hyphen)));
+            if c == '\0' {
+                inner.push('\u{FFFD}');
+            } else if escape_as_code_point {
+                inner.push('\\');
+                inner.push_hex(u32::from(c));
+                inner.push(' ');
+            } else if i == 0 && s == "-" {
+                inner.push_str("\\-");
+            } else if is_css_ident_char(c) {
+                inner.push(c);
+            } else {
+                inner.push('\\');
+                inner.push(c);
+            }
+        }
+        Self { inner }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inputs which are hard to handle.
+    const AWKWARD: &[&str] = &[
+        "",
+        "-",
+        "--",
+        "-1",
+        "1",
+        "1a",
+        " ",
+        "a b",
+        ":",
+        ":a:",
+        "a:b",
+        "\u{300}",
+        "\u{300}a",
+        "\0",
+        "\x01",
+        "\x7F",
+        "\\",
+        "a\\b",
+        "-\\",
+        "\r\n",
+        "\u{E9}",
+        "-\u{E9}",
+        "a.b#c",
+        "\t",
+        "_",
+        "\u{3042} \u{3044}",
+        "\u{FFFE}",
+    ];
+
+    /// Asserts that the result is an error at the given offset for the given reason.
+    #[track_caller]
+    fn assert_invalid(
+        result: Result<impl fmt::Debug, InvalidIdentError>,
+        kind: IdentKind,
+        offset: usize,
+        reason: Reason,
+    ) {
+        let e = result.unwrap_err();
+        assert_eq!((e.kind(), e.offset(), e.reason), (kind, offset, reason));
+    }
+
+    #[test]
+    fn xml_name_errors() {
+        use Reason::*;
+
+        let qname = IdentKind::XmlQName;
+        assert_invalid(XmlName::new(""), qname, 0, UnexpectedEnd);
+        assert_invalid(XmlName::new("1a"), qname, 0, InvalidStart('1'));
+        assert_invalid(XmlName::new("-"), qname, 0, InvalidStart('-'));
+        assert_invalid(XmlName::new("\u{300}a"), qname, 0, InvalidStart('\u{300}'));
+        assert_invalid(XmlName::new("a b"), qname, 1, InvalidChar(' '));
+        assert_invalid(XmlName::new("\u{3042} b"), qname, 3, InvalidChar(' '));
+        assert_invalid(XmlName::new(":a"), qname, 0, InvalidStart(':'));
+        assert_invalid(XmlName::new("a:"), qname, 2, UnexpectedEnd);
+        assert_invalid(XmlName::new("a:1"), qname, 2, InvalidStart('1'));
+        assert_invalid(XmlName::new("ab:c:d"), qname, 4, InvalidChar(':'));
+
+        let ncname = IdentKind::XmlNcName;
+        assert_invalid(XmlName::new_ncname(""), ncname, 0, UnexpectedEnd);
+        assert_invalid(XmlName::new_ncname("a:b"), ncname, 1, InvalidChar(':'));
+
+        for &valid in &["a", "_x-1.2", "\u{E9}\u{300}", "a:b", "xml:lang"] {
+            assert!(XmlName::new(valid).is_ok(), "{:?}", valid);
+        }
+        assert!(!XmlName::new("a:b").unwrap().is_ncname());
+        assert!(XmlName::new_ncname("a").unwrap().is_ncname());
+    }
+
+    #[test]
+    fn html_id_errors() {
+        use Reason::*;
+
+        let kind = IdentKind::HtmlId;
+        assert_invalid(HtmlId::new(""), kind, 0, UnexpectedEnd);
+        assert_invalid(HtmlId::new(" "), kind, 0, InvalidChar(' '));
+        assert_invalid(HtmlId::new("a b"), kind, 1, InvalidChar(' '));
+        assert_invalid(HtmlId::new("\u{3042}\t"), kind, 3, InvalidChar('\t'));
+
+        for &valid in &["1", "-", "a.b#c", "\u{3042}", "\u{A0}"] {
+            assert!(HtmlId::new(valid).is_ok(), "{:?}", valid);
+        }
+    }
+
+    #[test]
+    fn css_ident_errors() {
+        use Reason::*;
+
+        let kind = IdentKind::CssIdent;
+        assert_invalid(CssIdent::new(""), kind, 0, UnexpectedEnd);
+        assert_invalid(CssIdent::new("-"), kind, 1, UnexpectedEnd);
+        assert_invalid(CssIdent::new("-1"), kind, 1, InvalidStart('1'));
+        assert_invalid(CssIdent::new("1a"), kind, 0, InvalidStart('1'));
+        assert_invalid(CssIdent::new("a b"), kind, 1, InvalidChar(' '));
+        assert_invalid(CssIdent::new("a.b"), kind, 1, InvalidChar('.'));
+        assert_invalid(CssIdent::new("\u{E9}\0"), kind, 2, InvalidChar('\0'));
+        assert_invalid(CssIdent::new("a\\"), kind, 1, InvalidEscape);
+        assert_invalid(CssIdent::new("ab\\\n"), kind, 2, InvalidEscape);
+        assert_invalid(CssIdent::new("\\31  a"), kind, 4, InvalidChar(' '));
+
+        for &valid in &[
+            "a",
+            "--",
+            "--1",
+            "-a",
+            "-_",
+            "_1",
+            "\\31 a",
+            "\\31\r\na",
+            "\\31",
+            "a\\.b",
+            "-\\31 ",
+            "\u{E9}",
+            "-\u{E9}",
+            "\\\u{3042}",
+        ] {
+            assert!(CssIdent::new(valid).is_ok(), "{:?}", valid);
+        }
+    }
+
+    #[test]
+    fn sanitize_results_are_valid() {
+        for &s in AWKWARD {
+            let xml = XmlName::sanitize(s);
+            assert!(
+                XmlName::new_ncname(xml.as_str()).is_ok(),
+                "{:?} -> {:?}",
+                s,
+                xml
+            );
+            let html = HtmlId::sanitize(s);
+            assert!(HtmlId::new(html.as_str()).is_ok(), "{:?} -> {:?}", s, html);
+            let css = CssIdent::sanitize(s);
+            assert!(CssIdent::new(css.as_str()).is_ok(), "{:?} -> {:?}", s, css);
+        }
+    }
+
+    #[test]
+    fn sanitize_results() {
+        assert_eq!(XmlName::sanitize(""), "_");
+        assert_eq!(XmlName::sanitize("valid"), "valid");
+        assert_eq!(XmlName::sanitize("-a"), "_-a");
+        assert_eq!(XmlName::sanitize("1 a:b"), "_1_a_b");
+        assert_eq!(XmlName::sanitize("\u{300}"), "_\u{300}");
+
+        assert_eq!(HtmlId::sanitize(""), "_");
+        assert_eq!(HtmlId::sanitize("a b\tc"), "a_b_c");
+
+        assert_eq!(CssIdent::sanitize(""), "_");
+        assert_eq!(CssIdent::sanitize("-"), "\\-");
+        assert_eq!(CssIdent::sanitize("--"), "--");
+        assert_eq!(CssIdent::sanitize("1a"), "\\31 a");
+        assert_eq!(CssIdent::sanitize("-1"), "-\\31 ");
+        assert_eq!(CssIdent::sanitize("a b.c"), "a\\ b\\.c");
+        assert_eq!(CssIdent::sanitize("a\0\x01"), "a\u{FFFD}\\1 ");
+    }
+}
@@ -0,0 +1,347 @@
+//! I/O.
+
+use std::{
+    io::{self, BufRead, Write},
+    iter::FusedIterator,
+    str,
+};
+
+use crate::{error::SmallFromUtf8Error, string::SmallString};
+
+/// Returns the error for the invalid or incomplete UTF-8 sequence.
+///
+/// The inner error is `SmallFromUtf8Error` which has the offending bytes.
+fn invalid_utf8(bytes: &[u8]) -> io::Error {
+    let error = str::from_utf8(bytes).expect_err("Should never fail: the sequence is invalid");
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        SmallFromUtf8Error::from_slice(bytes, error),
+    )
+}
+
+/// Returns the length of the UTF-8 sequence starting with the given byte.
+///
+/// Returns `0` if the byte cannot start a sequence.
+#[inline]
+fn utf8_sequence_len(first: u8) -> usize {
+    match first {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    }
+}
+
+/// `io::Write` sink which writes into a `SmallString`.
+///
+/// Written bytes are validated as UTF-8 on the fly.
+/// A character can be split across multiple `write` calls: the incomplete
+/// sequence at the end of a write is kept until the following bytes come.
+///
+/// If an invalid sequence is found, `write` consumes only the valid bytes before it
+/// and returns their count, and the following `write` starting with the invalid
+/// sequence fails with `io::ErrorKind::InvalidData`.
+/// So `write_all` fails with the valid part already written to the string.
+/// The inner error of the failure is `SmallFromUtf8Error` with the invalid sequence.
+#[derive(Default, Debug, Clone)]
+pub struct SmallStringWriter {
+    /// String.
+    string: SmallString,
+    /// Incomplete UTF-8 sequence.
+    pending: [u8; 4],
+    /// Length of the incomplete UTF-8 sequence.
+    pending_len: usize,
+}
+
+impl SmallStringWriter {
+    /// Creates a new writer with an empty string.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the string written so far, excluding the incomplete sequence at the end.
+    #[inline]
+    pub fn get_ref(&self) -> &SmallString {
+        &self.string
+    }
+
+    /// Returns the string.
+    ///
+    /// Returns an error if the written bytes end with an incomplete UTF-8 sequence.
+    #[inline]
+    pub fn finish(self) -> io::Result<SmallString> {
+        if self.pending_len != 0 {
+            return Err(invalid_utf8(&self.pending[..self.pending_len]));
+        }
+
+        Ok(self.string)
+    }
+
+    /// Completes the pending sequence using the head of the given bytes,
+    /// and returns the rest of the bytes.
+    fn complete_pending<'a>(&mut self, buf: &'a [u8]) -> io::Result<&'a [u8]> {
+        let seq_len = utf8_sequence_len(self.pending[0]);
+        let consumed = (seq_len - self.pending_len).min(buf.len());
+        self.pending[self.pending_len..(self.pending_len + consumed)]
+            .copy_from_slice(&buf[..consumed]);
+        self.pending_len += consumed;
+
+        match str::from_utf8(&self.pending[..self.pending_len]) {
+            Ok(s) => {
+                self.string.push_str(s);
+                self.pending_len = 0;
+            }
+            // Still incomplete.
+            Err(e) if e.error_len().is_none() => {}
+            Err(_) => return Err(invalid_utf8(&self.pending[..self.pending_len])),
+        }
+
+        Ok(&buf[consumed..])
+    }
+}
+
+impl From<SmallString> for SmallStringWriter {
+    /// Creates a new writer which appends to the given string.
+    #[inline]
+    fn from(string: SmallString) -> Self {
+        Self {
+            string,
+            ..Default::default()
+        }
+    }
+}
+
+impl io::Write for SmallStringWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        if self.pending_len != 0 {
+            rest = self.complete_pending(rest)?;
+            if self.pending_len != 0 {
+                debug_assert!(rest.is_empty());
+                return Ok(buf.len());
+            }
+        }
+
+        match str::from_utf8(rest) {
+            Ok(s) => self.string.push_str(s),
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                self.string
+                    .push_str(str::from_utf8(valid).expect("Should never fail: already validated"));
+                if let Some(error_len) = e.error_len() {
+                    // Report the valid prefix as written, and fail on the next write.
+                    let written = buf.len() - invalid.len();
+                    if written == 0 {
+                        return Err(invalid_utf8(&invalid[..error_len]));
+                    }
+                    return Ok(written);
+                }
+                // Incomplete sequence at the end.
+                self.pending[..invalid.len()].copy_from_slice(invalid);
+                self.pending_len = invalid.len();
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Line ending handling on reading lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// Keeps `\n` or `\r\n` at the end of lines.
+    Keep,
+    /// Strips `\n` or `\r\n` at the end of lines.
+    Strip,
+}
+
+/// Reads a line from the reader, and appends it to `dest`.
+///
+/// Returns the number of bytes consumed, including the line ending.
+/// On error, `dest` is restored to its original content.
+pub(crate) fn read_line<R: BufRead + ?Sized>(
+    dest: &mut SmallString,
+    reader: &mut R,
+    line_ending: LineEnding,
+) -> io::Result<usize> {
+    let orig_len = dest.len();
+    // Appends to `dest` through the writer, in order to validate UTF-8
+    // directly from the reader's buffer.
+    let mut writer = SmallStringWriter::from(dest.take());
+    let mut consumed = 0;
+    let result = loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => break Err(e),
+        };
+        let (chunk, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(newline) => (&buf[..=newline], true),
+            None => (buf, buf.is_empty()),
+        };
+        let chunk_len = chunk.len();
+        let written = writer.write_all(chunk);
+        reader.consume(chunk_len);
+        consumed += chunk_len;
+        if let Err(e) = written {
+            break Err(e);
+        }
+        if done {
+            break Ok(());
+        }
+    };
+    let incomplete = match writer.pending_len {
+        0 => None,
+        len => Some(invalid_utf8(&writer.pending[..len])),
+    };
+    *dest = writer.string;
+
+    if let Some(e) = result.err().or(incomplete) {
+        dest.truncate(orig_len);
+        return Err(e);
+    }
+    if line_ending == LineEnding::Strip && dest.len() > orig_len && dest.ends_with('\n') {
+        dest.pop();
+        if dest.len() > orig_len && dest.ends_with('\r') {
+            dest.pop();
+        }
+    }
+
+    Ok(consumed)
+}
+
+/// Extension trait for `BufRead`.
+pub trait BufReadExt: BufRead {
+    /// Returns an iterator of lines as `SmallString`s.
+    ///
+    /// By default, line endings are stripped as `BufRead::lines()` does.
+    #[inline]
+    fn lines_small(self) -> LinesSmall<Self>
+    where
+        Self: Sized,
+    {
+        LinesSmall {
+            reader: self,
+            line_ending: LineEnding::Strip,
+            finished: false,
+        }
+    }
+}
+
+impl<R: BufRead> BufReadExt for R {}
+
+/// Iterator of lines as `SmallString`s.
+#[derive(Debug)]
+pub struct LinesSmall<R> {
+    /// Reader.
+    reader: R,
+    /// Line ending handling.
+    line_ending: LineEnding,
+    /// Whether the reader has reached EOF.
+    finished: bool,
+}
+
+impl<R> LinesSmall<R> {
+    /// Sets how to handle line endings.
+    #[inline]
+    pub fn line_ending(self, line_ending: LineEnding) -> Self {
+        Self {
+            line_ending,
+            ..self
+        }
+    }
+
+    /// Returns the inner reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for LinesSmall<R> {
+    type Item = io::Result<SmallString>;
+
+    /// Once EOF is reached, this returns `None` without reading further.
+    /// On error, the following call continues reading the rest.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let mut line = SmallString::new();
+        match read_line(&mut line, &mut self.reader, self.line_ending) {
+            Ok(0) => {
+                self.finished = true;
+                None
+            }
+            Ok(_) => Some(Ok(line)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<R: BufRead> FusedIterator for LinesSmall<R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_consumes_valid_prefix() {
+        let mut writer = SmallStringWriter::new();
+        assert_eq!(writer.write(b"ab\xFFcd").unwrap(), 2);
+        assert_eq!(writer.get_ref().as_str(), "ab");
+        let err = writer.write(b"\xFFcd").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.get_ref().unwrap().downcast_ref::<SmallFromUtf8Error>();
+        assert_eq!(inner.unwrap().as_bytes(), b"\xFF");
+
+        let mut writer = SmallStringWriter::new();
+        assert!(writer.write_all(b"ab\xFFcd").is_err());
+        assert_eq!(writer.get_ref().as_str(), "ab");
+    }
+
+    #[test]
+    fn write_keeps_incomplete_sequence() {
+        let mut writer = SmallStringWriter::new();
+        let bytes = "aあ".as_bytes();
+        assert_eq!(writer.write(&bytes[..2]).unwrap(), 2);
+        assert_eq!(writer.write(&bytes[2..]).unwrap(), 2);
+        assert_eq!(writer.finish().unwrap().as_str(), "aあ");
+    }
+
+    /// Reader which returns more data after it once returned EOF.
+    struct Resuming {
+        /// Chunks to return, `None` for EOF.
+        chunks: Vec<Option<&'static [u8]>>,
+    }
+
+    impl io::Read for Resuming {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let chunk = match self.chunks.pop() {
+                Some(Some(chunk)) => chunk,
+                Some(None) | None => return Ok(0),
+            };
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn lines_small_is_fused() {
+        let reader = Resuming {
+            chunks: vec![Some(b"b\n"), None, Some(b"a\n")],
+        };
+        let mut lines = io::BufReader::new(reader).lines_small();
+        assert_eq!(lines.next().unwrap().unwrap().as_str(), "a");
+        assert!(lines.next().is_none());
+        assert!(lines.next().is_none());
+    }
+}
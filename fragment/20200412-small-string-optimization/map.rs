@@ -0,0 +1,927 @@
+//! Map keyed by `SmallString`.
+//!
+//! Keys are stored in the map's own entry list, so short keys are inlined
+//! into the list without separate allocations.
+//! Entries are looked up by `&str` without constructing a key.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::BuildHasher,
+    iter::{FromIterator, FusedIterator},
+    ops::{Index, IndexMut},
+    slice, vec,
+};
+
+use crate::string::{IntoSmallString, SmallString};
+
+/// Maximum number of entries looked up by linear search.
+///
+/// Typical JSON objects are small, and building the index table is a waste
+/// for them.
+const LINEAR_SEARCH_MAX: usize = 8;
+
+/// Empty slot in the index table.
+const EMPTY: usize = usize::MAX;
+
+/// Order of keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyOrder {
+    /// Keys are kept in insertion order.
+    ///
+    /// Removing an entry takes time proportional to the number of entries.
+    Insertion,
+    /// Keys are in insertion order until an entry is removed.
+    ///
+    /// Removing an entry moves the last entry to its position.
+    Unspecified,
+}
+
+impl Default for KeyOrder {
+    #[inline]
+    fn default() -> Self {
+        KeyOrder::Unspecified
+    }
+}
+
+/// Entry of the map.
+#[derive(Debug, Clone)]
+struct Bucket<V> {
+    /// Hash value of the key.
+    hash: u64,
+    /// Key.
+    key: SmallString,
+    /// Value.
+    value: V,
+}
+
+/// Map keyed by `SmallString`.
+#[derive(Clone)]
+pub struct SmallStringMap<V, S = RandomState> {
+    /// Entries.
+    entries: Vec<Bucket<V>>,
+    /// Open addressing table of indices into `entries`.
+    ///
+    /// This is empty while the entries are few, and otherwise has a length of
+    /// power of two, at least twice the number of entries.
+    indices: Vec<usize>,
+    /// Hasher builder.
+    build_hasher: S,
+    /// Order of keys.
+    key_order: KeyOrder,
+}
+
+impl<V> SmallStringMap<V> {
+    /// Creates a new empty map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new empty map with the given capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, Default::default())
+    }
+}
+
+impl<V, S> SmallStringMap<V, S> {
+    /// Creates a new empty map with the given hasher builder.
+    #[inline]
+    pub fn with_hasher(build_hasher: S) -> Self {
+        Self::with_capacity_and_hasher(0, build_hasher)
+    }
+
+    /// Creates a new empty map with the given capacity and hasher builder.
+    #[inline]
+    pub fn with_capacity_and_hasher(capacity: usize, build_hasher: S) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            indices: Vec::new(),
+            build_hasher,
+            key_order: KeyOrder::default(),
+        }
+    }
+
+    /// Sets the order of keys.
+    #[inline]
+    pub fn key_order(self, key_order: KeyOrder) -> Self {
+        Self { key_order, ..self }
+    }
+
+    /// Returns the number of entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map has no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    /// Returns an iterator of entries.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Returns an iterator of entries, with mutable references to the values.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, V> {
+        IterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    /// Returns an iterator of keys.
+    #[inline]
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator of values.
+    #[inline]
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator of mutable references to the values.
+    #[inline]
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Returns the mask of the index table.
+    #[inline]
+    fn mask(&self) -> usize {
+        debug_assert!(self.indices.len().is_power_of_two());
+        self.indices.len() - 1
+    }
+
+    /// Returns the index of the entry with the given key.
+    fn find(&self, hash: u64, key: &str) -> Option<usize> {
+        let matches = |bucket: &Bucket<V>| bucket.hash == hash && bucket.key.as_str() == key;
+        if self.indices.is_empty() {
+            return self.entries.iter().position(matches);
+        }
+
+        let mask = self.mask();
+        let mut pos = hash as usize & mask;
+        loop {
+            let index = self.indices[pos];
+            if index == EMPTY {
+                return None;
+            }
+            if matches(&self.entries[index]) {
+                return Some(index);
+            }
+            pos = (pos + 1) & mask;
+        }
+    }
+
+    /// Returns the position of the given entry in the index table.
+    fn slot_of(&self, index: usize) -> usize {
+        let mask = self.mask();
+        let mut pos = self.entries[index].hash as usize & mask;
+        while self.indices[pos] != index {
+            debug_assert_ne!(self.indices[pos], EMPTY, "The entry should be indexed");
+            pos = (pos + 1) & mask;
+        }
+        pos
+    }
+
+    /// Adds the entry to the index table.
+    fn insert_index(&mut self, hash: u64, index: usize) {
+        let mask = self.mask();
+        let mut pos = hash as usize & mask;
+        while self.indices[pos] != EMPTY {
+            pos = (pos + 1) & mask;
+        }
+        self.indices[pos] = index;
+    }
+
+    /// Rebuilds the index table for the current entries.
+    fn rebuild_indices(&mut self) {
+        let table_len = (self.entries.len() * 2).next_power_of_two();
+        self.indices.clear();
+        self.indices.resize(table_len, EMPTY);
+        for index in 0..self.entries.len() {
+            self.insert_index(self.entries[index].hash, index);
+        }
+    }
+
+    /// Removes the given slot from the index table.
+    ///
+    /// Following entries in the same cluster are shifted back, so that
+    /// lookups do not need tombstones.
+    fn remove_slot(&mut self, slot: usize) {
+        let mask = self.mask();
+        let mut hole = slot;
+        let mut pos = (slot + 1) & mask;
+        loop {
+            let index = self.indices[pos];
+            if index == EMPTY {
+                break;
+            }
+            let ideal = self.entries[index].hash as usize & mask;
+            // Move the entry if its ideal position is not in `(hole, pos]`.
+            if pos.wrapping_sub(ideal) & mask >= pos.wrapping_sub(hole) & mask {
+                self.indices[hole] = index;
+                hole = pos;
+            }
+            pos = (pos + 1) & mask;
+        }
+        self.indices[hole] = EMPTY;
+    }
+
+    /// Appends a new entry, and returns its index.
+    fn push_entry(&mut self, hash: u64, key: SmallString, value: V) -> usize {
+        let index = self.entries.len();
+        self.entries.push(Bucket { hash, key, value });
+        if self.indices.len() >= self.entries.len() * 2 {
+            self.insert_index(hash, index);
+        } else if self.entries.len() > LINEAR_SEARCH_MAX {
+            self.rebuild_indices();
+        }
+        index
+    }
+
+    /// Removes the entry at the given index.
+    fn remove_at(&mut self, index: usize) -> (SmallString, V) {
+        if !self.indices.is_empty() {
+            let slot = self.slot_of(index);
+            self.remove_slot(slot);
+        }
+
+        let bucket = match self.key_order {
+            KeyOrder::Insertion => {
+                let bucket = self.entries.remove(index);
+                for i in self.indices.iter_mut() {
+                    if *i != EMPTY && *i > index {
+                        *i -= 1;
+                    }
+                }
+                bucket
+            }
+            KeyOrder::Unspecified => {
+                let last = self.entries.len() - 1;
+                if index != last && !self.indices.is_empty() {
+                    let slot = self.slot_of(last);
+                    self.indices[slot] = index;
+                }
+                self.entries.swap_remove(index)
+            }
+        };
+
+        (bucket.key, bucket.value)
+    }
+}
+
+impl<V, S: BuildHasher> SmallStringMap<V, S> {
+    /// Returns the hash value of the key.
+    #[inline]
+    fn hash(&self, key: &str) -> u64 {
+        self.build_hasher.hash_one(key)
+    }
+
+    /// Returns `true` if the map has an entry with the key.
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.find(self.hash(key), key).is_some()
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.find(self.hash(key), key)
+            .map(|index| &self.entries[index].value)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    #[inline]
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let index = self.find(self.hash(key), key)?;
+        Some(&mut self.entries[index].value)
+    }
+
+    /// Returns the stored key and the value corresponding to the key.
+    #[inline]
+    pub fn get_key_value(&self, key: &str) -> Option<(&SmallString, &V)> {
+        let bucket = &self.entries[self.find(self.hash(key), key)?];
+        Some((&bucket.key, &bucket.value))
+    }
+
+    /// Inserts the entry, and returns the old value if the key already exists.
+    ///
+    /// The existing key is kept, and the new key is dropped in that case.
+    pub fn insert(&mut self, key: impl IntoSmallString, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key, inserting
+    /// the value returned by `f` if the key does not exist.
+    ///
+    /// The key is converted to `SmallString` only if it is inserted.
+    pub fn get_or_insert_with<F>(&mut self, key: &str, f: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        let hash = self.hash(key);
+        let index = match self.find(hash, key) {
+            Some(index) => index,
+            None => self.push_entry(hash, key.into(), f()),
+        };
+        &mut self.entries[index].value
+    }
+
+    /// Removes the entry with the key, and returns the value.
+    #[inline]
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// Removes the entry with the key, and returns the stored key and the value.
+    #[inline]
+    pub fn remove_entry(&mut self, key: &str) -> Option<(SmallString, V)> {
+        let index = self.find(self.hash(key), key)?;
+        Some(self.remove_at(index))
+    }
+
+    /// Retains only the entries which the predicate returns `true` for.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str, &mut V) -> bool,
+    {
+        self.entries
+            .retain_mut(|bucket| f(&bucket.key, &mut bucket.value));
+        if self.entries.len() > LINEAR_SEARCH_MAX {
+            self.rebuild_indices();
+        } else {
+            self.indices.clear();
+        }
+    }
+
+    /// Returns the entry of the key for in-place manipulation.
+    pub fn entry(&mut self, key: impl IntoSmallString) -> Entry<'_, V, S> {
+        let key = key.into_small_string();
+        let hash = self.hash(&key);
+        match self.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                hash,
+                key,
+            }),
+        }
+    }
+}
+
+impl<V, S: Default> Default for SmallStringMap<V, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<V: fmt::Debug, S> fmt::Debug for SmallStringMap<V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V: PartialEq, S: BuildHasher> PartialEq for SmallStringMap<V, S> {
+    /// Compares the entries regardless of the order.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<V: Eq, S: BuildHasher> Eq for SmallStringMap<V, S> {}
+
+impl<V, S: BuildHasher> Index<&str> for SmallStringMap<V, S> {
+    type Output = V;
+
+    /// Returns the value corresponding to the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key does not exist.
+    #[inline]
+    fn index(&self, key: &str) -> &V {
+        self.get(key).expect("The key should exist in the map")
+    }
+}
+
+impl<V, S: BuildHasher> IndexMut<&str> for SmallStringMap<V, S> {
+    /// Returns the value corresponding to the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key does not exist.
+    #[inline]
+    fn index_mut(&mut self, key: &str) -> &mut V {
+        self.get_mut(key).expect("The key should exist in the map")
+    }
+}
+
+impl<K: IntoSmallString, V, S: BuildHasher> Extend<(K, V)> for SmallStringMap<V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: IntoSmallString, V, S: BuildHasher + Default> FromIterator<(K, V)>
+    for SmallStringMap<V, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, V, S> IntoIterator for &'a SmallStringMap<V, S> {
+    type Item = (&'a str, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V, S> IntoIterator for &'a mut SmallStringMap<V, S> {
+    type Item = (&'a str, &'a mut V);
+    type IntoIter = IterMut<'a, V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<V, S> IntoIterator for SmallStringMap<V, S> {
+    type Item = (SmallString, V);
+    type IntoIter = IntoIter<V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+/// Entry of the map, which may or may not exist.
+pub enum Entry<'a, V, S = RandomState> {
+    /// Existing entry.
+    Occupied(OccupiedEntry<'a, V, S>),
+    /// Nonexistent entry.
+    Vacant(VacantEntry<'a, V, S>),
+}
+
+impl<'a, V, S> Entry<'a, V, S> {
+    /// Returns the key.
+    #[inline]
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Modifies the value if the entry exists.
+    #[inline]
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+
+    /// Inserts the given value if the entry does not exist, and returns a mutable
+    /// reference to the value.
+    #[inline]
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the value returned by `f` if the entry does not exist, and returns
+    /// a mutable reference to the value.
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Inserts the default value if the entry does not exist, and returns a mutable
+    /// reference to the value.
+    #[inline]
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<V: fmt::Debug, S> fmt::Debug for Entry<'_, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Occupied(entry) => f.debug_tuple("Occupied").field(entry).finish(),
+            Entry::Vacant(entry) => f.debug_tuple("Vacant").field(entry).finish(),
+        }
+    }
+}
+
+/// Existing entry of the map.
+pub struct OccupiedEntry<'a, V, S = RandomState> {
+    /// Map.
+    map: &'a mut SmallStringMap<V, S>,
+    /// Index of the entry.
+    index: usize,
+}
+
+impl<'a, V, S> OccupiedEntry<'a, V, S> {
+    /// Returns the stored key.
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.map.entries[self.index].key
+    }
+
+    /// Returns a reference to the value.
+    #[inline]
+    pub fn get(&self) -> &V {
+        &self.map.entries[self.index].value
+    }
+
+    /// Returns a mutable reference to the value.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.entries[self.index].value
+    }
+
+    /// Returns a mutable reference to the value, with the lifetime of the map.
+    #[inline]
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.entries[self.index].value
+    }
+
+    /// Replaces the value, and returns the old value.
+    #[inline]
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry, and returns the value.
+    #[inline]
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry, and returns the stored key and the value.
+    #[inline]
+    pub fn remove_entry(self) -> (SmallString, V) {
+        self.map.remove_at(self.index)
+    }
+}
+
+impl<V: fmt::Debug, S> fmt::Debug for OccupiedEntry<'_, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key())
+            .field("value", self.get())
+            .finish()
+    }
+}
+
+/// Nonexistent entry of the map.
+pub struct VacantEntry<'a, V, S = RandomState> {
+    /// Map.
+    map: &'a mut SmallStringMap<V, S>,
+    /// Hash value of the key.
+    hash: u64,
+    /// Key.
+    key: SmallString,
+}
+
+impl<'a, V, S> VacantEntry<'a, V, S> {
+    /// Returns the key.
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the key.
+    #[inline]
+    pub fn into_key(self) -> SmallString {
+        self.key
+    }
+
+    /// Inserts the value, and returns a mutable reference to it.
+    #[inline]
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.map.push_entry(self.hash, self.key, value);
+        &mut self.map.entries[index].value
+    }
+}
+
+impl<V, S> fmt::Debug for VacantEntry<'_, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key()).finish()
+    }
+}
+
+/// Iterator of entries of the map.
+#[derive(Debug, Clone)]
+pub struct Iter<'a, V> {
+    /// Inner iterator.
+    inner: slice::Iter<'a, Bucket<V>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a str, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| (b.key.as_str(), &b.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for Iter<'_, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|b| (b.key.as_str(), &b.value))
+    }
+}
+
+impl<V> ExactSizeIterator for Iter<'_, V> {}
+
+impl<V> FusedIterator for Iter<'_, V> {}
+
+/// Iterator of entries of the map, with mutable references to the values.
+#[derive(Debug)]
+pub struct IterMut<'a, V> {
+    /// Inner iterator.
+    inner: slice::IterMut<'a, Bucket<V>>,
+}
+
+impl<'a, V> Iterator for IterMut<'a, V> {
+    type Item = (&'a str, &'a mut V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| (b.key.as_str(), &mut b.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for IterMut<'_, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|b| (b.key.as_str(), &mut b.value))
+    }
+}
+
+impl<V> ExactSizeIterator for IterMut<'_, V> {}
+
+impl<V> FusedIterator for IterMut<'_, V> {}
+
+/// Owning iterator of entries of the map.
+#[derive(Debug)]
+pub struct IntoIter<V> {
+    /// Inner iterator.
+    inner: vec::IntoIter<Bucket<V>>,
+}
+
+impl<V> Iterator for IntoIter<V> {
+    type Item = (SmallString, V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|b| (b.key, b.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<V> DoubleEndedIterator for IntoIter<V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|b| (b.key, b.value))
+    }
+}
+
+impl<V> ExactSizeIterator for IntoIter<V> {}
+
+impl<V> FusedIterator for IntoIter<V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        collections::HashMap,
+        hash::{BuildHasherDefault, Hasher},
+    };
+
+    /// Xorshift pseudo-random number generator.
+    struct Rng(u64);
+
+    impl Rng {
+        /// Returns a random number less than `n`.
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    /// Hasher which makes most keys collide.
+    #[derive(Default)]
+    struct CollidingHasher(u64);
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            self.0 % 3
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 += bytes.len() as u64;
+        }
+    }
+
+    /// Returns the keys used in tests, including ones longer than the inline capacity.
+    fn keys() -> Vec<String> {
+        (0..24)
+            .map(|i| match i % 3 {
+                0 => format!("k{}", i),
+                1 => format!("key-{}", i),
+                _ => format!("a long key which is stored on the heap #{}", i),
+            })
+            .collect()
+    }
+
+    /// Applies random operations to the map and `HashMap`, and compares them.
+    fn check_against_hash_map<S: BuildHasher>(build_hasher: S, key_order: KeyOrder, seed: u64) {
+        let keys = keys();
+        let mut rng = Rng(seed);
+        let mut map = SmallStringMap::with_hasher(build_hasher).key_order(key_order);
+        let mut oracle = HashMap::new();
+        // Keys in insertion order.
+        let mut order: Vec<&str> = Vec::new();
+        for step in 0..5000 {
+            let key = keys[rng.below(keys.len())].as_str();
+            let value = step;
+            match rng.below(16) {
+                0..=4 => {
+                    let old = oracle.insert(key, value);
+                    if old.is_none() {
+                        order.push(key);
+                    }
+                    assert_eq!(map.insert(key, value), old);
+                }
+                5..=7 => {
+                    let removed = oracle.remove(key);
+                    order.retain(|k| *k != key);
+                    assert_eq!(map.remove(key), removed);
+                }
+                8 => {
+                    let removed = oracle.remove_entry(key);
+                    order.retain(|k| *k != key);
+                    let removed_map = map.remove_entry(key);
+                    assert_eq!(removed_map.as_ref().map(|(k, v)| (k.as_str(), *v)), removed);
+                }
+                9 | 10 => {
+                    let expected = *oracle.entry(key).or_insert_with(|| {
+                        order.push(key);
+                        value
+                    });
+                    assert_eq!(*map.get_or_insert_with(key, || value), expected);
+                }
+                11 | 12 => match map.entry(key) {
+                    Entry::Occupied(entry) if value % 2 == 0 => {
+                        assert_eq!(Some(entry.remove()), oracle.remove(key));
+                        order.retain(|k| *k != key);
+                    }
+                    entry => {
+                        if !oracle.contains_key(key) {
+                            order.push(key);
+                        }
+                        let expected = oracle.entry(key).and_modify(|v| *v += 1).or_insert(value);
+                        assert_eq!(*entry.and_modify(|v| *v += 1).or_insert(value), *expected);
+                    }
+                },
+                13 | 14 => {
+                    let modulus = 2 + rng.below(3);
+                    oracle.retain(|_, v| *v % modulus != 0);
+                    order.retain(|k| oracle.contains_key(k));
+                    map.retain(|_, v| *v % modulus != 0);
+                }
+                _ => {
+                    if rng.below(8) == 0 {
+                        oracle.clear();
+                        order.clear();
+                        map.clear();
+                    }
+                }
+            }
+
+            assert_eq!(map.len(), oracle.len());
+            for key in &keys {
+                assert_eq!(map.get(key), oracle.get(key.as_str()), "step {}", step);
+                assert_eq!(map.contains_key(key), oracle.contains_key(key.as_str()));
+            }
+            match key_order {
+                KeyOrder::Insertion => {
+                    assert_eq!(map.keys().collect::<Vec<_>>(), order);
+                }
+                KeyOrder::Unspecified => {
+                    let mut map_keys = map.keys().collect::<Vec<_>>();
+                    map_keys.sort_unstable();
+                    let mut oracle_keys = oracle.keys().copied().collect::<Vec<_>>();
+                    oracle_keys.sort_unstable();
+                    assert_eq!(map_keys, oracle_keys);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_hash_map() {
+        for &key_order in &[KeyOrder::Insertion, KeyOrder::Unspecified] {
+            for seed in 1..=4 {
+                check_against_hash_map(RandomState::new(), key_order, seed);
+                check_against_hash_map(
+                    BuildHasherDefault::<CollidingHasher>::default(),
+                    key_order,
+                    seed,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut map = SmallStringMap::new().key_order(KeyOrder::Insertion);
+        let keys = keys();
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(key.as_str(), i);
+        }
+        map.remove(&keys[0]);
+        map.remove(&keys[10]);
+        map.insert(keys[0].as_str(), 0);
+        let expected = keys[1..10]
+            .iter()
+            .chain(&keys[11..])
+            .chain(Some(&keys[0]))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        assert_eq!(map.keys().collect::<Vec<_>>(), expected);
+        assert_eq!(map.iter().map(|(k, _)| k).collect::<Vec<_>>(), expected);
+        assert_eq!(
+            map.into_iter()
+                .map(|(k, _)| k.into_string())
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
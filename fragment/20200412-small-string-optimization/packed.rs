@@ -0,0 +1,1234 @@
+//! String type with packed 6-bit encoding for identifier-like strings.
+//!
+//! Ids and class names are mostly ASCII letters, digits, `-`, and `_`.
+//! `PackedSmallString` stores such strings in 6 bits per character, so up to
+//! [`PACKED_CAPACITY`] characters fit in 24 bytes without allocations.
+//! Other strings up to [`UTF8_CAPACITY`] bytes are stored inline as UTF-8, and
+//! longer ones are stored on the heap as `String` is.
+//!
+//! Packed strings cannot be borrowed as `&str` directly. `as_str()` returns
+//! [`PackedStr`], a view which decodes packed strings into a buffer on the stack.
+//!
+//! [`PACKED_CAPACITY`]: constant.PACKED_CAPACITY.html
+//! [`UTF8_CAPACITY`]: constant.UTF8_CAPACITY.html
+//! [`PackedStr`]: struct.PackedStr.html
+
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    slice, str,
+};
+
+use crate::string::SmallString;
+
+/// Size of the representation in bytes.
+const REPR_BYTES: usize = mem::size_of::<String>();
+
+/// Position of the tag byte in the inline representations.
+///
+/// The tag byte overlaps the most significant byte of the heap capacity.
+#[cfg(target_endian = "little")]
+const TAG_INDEX: usize = REPR_BYTES - 1;
+
+/// Position of the tag byte in the inline representations.
+///
+/// The tag byte overlaps the most significant byte of the heap capacity.
+#[cfg(target_endian = "big")]
+const TAG_INDEX: usize = 0;
+
+/// Start position of the codes or UTF-8 bytes in the inline representations.
+#[cfg(target_endian = "little")]
+const CODES_START: usize = 0;
+
+/// Start position of the codes or UTF-8 bytes in the inline representations.
+#[cfg(target_endian = "big")]
+const CODES_START: usize = 1;
+
+/// Bit of the tag byte which is set for the inline representations.
+///
+/// Heap capacities never exceed `isize::MAX`, so this bit is never set for them.
+const INLINE_TAG: u8 = 0x80;
+
+/// Bit of the tag byte which is set for the inline UTF-8 representation, in
+/// addition to [`INLINE_TAG`].
+///
+/// [`INLINE_TAG`]: constant.INLINE_TAG.html
+const UTF8_TAG: u8 = 0x40;
+
+/// Bits of the tag byte for the length of the inline representations.
+const LEN_MASK: u8 = 0x3f;
+
+/// Number of bytes for packed characters or UTF-8 bytes.
+const PACKED_BYTES: usize = REPR_BYTES - 1;
+
+/// Maximum length in bytes of strings stored inline as UTF-8.
+pub const UTF8_CAPACITY: usize = PACKED_BYTES;
+
+/// Number of bits per packed character.
+const BITS_PER_CHAR: usize = 6;
+
+/// Maximum length of packed strings.
+pub const PACKED_CAPACITY: usize = PACKED_BYTES * 8 / BITS_PER_CHAR;
+
+/// Characters which can be packed, in order of their codes.
+///
+/// Codes are in the same order as the characters, so packed strings can be
+/// compared code by code.
+const PACKED_ALPHABET: &[u8; 64] =
+    b"-0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
+
+/// Returns the code of the given byte, if it can be packed.
+#[inline]
+fn encode(b: u8) -> Option<u8> {
+    let code = match b {
+        b'-' => 0,
+        b'0'..=b'9' => b - b'0' + 1,
+        b'A'..=b'Z' => b - b'A' + 11,
+        b'_' => 37,
+        b'a'..=b'z' => b - b'a' + 38,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Returns `true` if the given string can be stored as a packed string.
+#[inline]
+pub fn is_packable(s: &str) -> bool {
+    s.len() <= PACKED_CAPACITY && s.bytes().all(|b| encode(b).is_some())
+}
+
+/// Packed string.
+///
+/// The tag byte has [`INLINE_TAG`] and the length, and the other bytes have
+/// the codes of the characters, 6 bits each in little-endian bit order.
+/// Bits after the last character are always zero.
+///
+/// [`INLINE_TAG`]: constant.INLINE_TAG.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+struct Packed {
+    /// Tag byte and codes.
+    bytes: [u8; REPR_BYTES],
+}
+
+impl Packed {
+    /// Empty packed string.
+    const EMPTY: Self = {
+        let mut bytes = [0; REPR_BYTES];
+        bytes[TAG_INDEX] = INLINE_TAG;
+        Self { bytes }
+    };
+
+    /// Returns the length.
+    #[inline]
+    fn len(&self) -> usize {
+        usize::from(self.bytes[TAG_INDEX] & LEN_MASK)
+    }
+
+    /// Sets the length.
+    #[inline]
+    fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= PACKED_CAPACITY);
+        self.bytes[TAG_INDEX] = INLINE_TAG | len as u8;
+    }
+
+    /// Returns the bytes for the codes.
+    #[inline]
+    fn codes(&self) -> &[u8] {
+        &self.bytes[CODES_START..(CODES_START + PACKED_BYTES)]
+    }
+
+    /// Returns the bytes for the codes.
+    #[inline]
+    fn codes_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes[CODES_START..(CODES_START + PACKED_BYTES)]
+    }
+
+    /// Returns the code at the given position.
+    #[inline]
+    fn code(&self, idx: usize) -> u8 {
+        debug_assert!(idx < self.len());
+        let codes = self.codes();
+        let bit = idx * BITS_PER_CHAR;
+        let byte = bit / 8;
+        let low = u16::from(codes[byte]);
+        let high = codes.get(byte + 1).map_or(0, |&b| u16::from(b));
+        (((high << 8) | low) >> (bit % 8)) as u8 & 0x3f
+    }
+
+    /// Appends the string if all of the characters can be packed into the
+    /// remaining space.
+    ///
+    /// Returns `false` and leaves `self` unchanged otherwise.
+    fn try_push_str(&mut self, s: &str) -> bool {
+        if s.len() > PACKED_CAPACITY - self.len() {
+            return false;
+        }
+        let mut pushed = *self;
+        let mut len = pushed.len();
+        for b in s.bytes() {
+            let code = match encode(b) {
+                Some(code) => u16::from(code),
+                None => return false,
+            };
+            let codes = pushed.codes_mut();
+            let bit = len * BITS_PER_CHAR;
+            let byte = bit / 8;
+            let bits = code << (bit % 8);
+            codes[byte] |= bits as u8;
+            if let Some(next) = codes.get_mut(byte + 1) {
+                *next |= (bits >> 8) as u8;
+            }
+            len += 1;
+        }
+        pushed.set_len(len);
+        *self = pushed;
+        true
+    }
+
+    /// Shortens the string to the given length.
+    fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.len() {
+            return;
+        }
+        // Keep unused bits zero.
+        let codes = self.codes_mut();
+        let bit = new_len * BITS_PER_CHAR;
+        let byte = bit / 8;
+        codes[byte] &= !(0xff << (bit % 8));
+        for b in &mut codes[(byte + 1)..] {
+            *b = 0;
+        }
+        self.set_len(new_len);
+    }
+
+    /// Decodes the string into the given buffer, and returns the decoded length.
+    #[inline]
+    fn decode(&self, buf: &mut [u8; PACKED_CAPACITY]) -> usize {
+        let len = self.len();
+        for (idx, dest) in buf[..len].iter_mut().enumerate() {
+            *dest = PACKED_ALPHABET[usize::from(self.code(idx))];
+        }
+        len
+    }
+}
+
+/// UTF-8 string stored inline.
+///
+/// The tag byte has [`INLINE_TAG`], [`UTF8_TAG`], and the length, and the
+/// other bytes have the string. Bytes after the string are always zero.
+///
+/// [`INLINE_TAG`]: constant.INLINE_TAG.html
+/// [`UTF8_TAG`]: constant.UTF8_TAG.html
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct InlineUtf8 {
+    /// Tag byte and string.
+    bytes: [u8; REPR_BYTES],
+}
+
+impl InlineUtf8 {
+    /// Empty string.
+    const EMPTY: Self = {
+        let mut bytes = [0; REPR_BYTES];
+        bytes[TAG_INDEX] = INLINE_TAG | UTF8_TAG;
+        Self { bytes }
+    };
+
+    /// Returns the length.
+    #[inline]
+    fn len(&self) -> usize {
+        usize::from(self.bytes[TAG_INDEX] & LEN_MASK)
+    }
+
+    /// Sets the length.
+    #[inline]
+    fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= UTF8_CAPACITY);
+        self.bytes[TAG_INDEX] = INLINE_TAG | UTF8_TAG | len as u8;
+    }
+
+    /// Returns the string slice.
+    #[inline]
+    fn as_str(&self) -> &str {
+        let bytes = &self.bytes[CODES_START..(CODES_START + self.len())];
+        // SAFETY: The bytes are only written by `try_push_str`, and
+        // `truncate` keeps them on a char boundary.
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+
+    /// Appends the string if it fits in the remaining space.
+    ///
+    /// Returns `false` and leaves `self` unchanged otherwise.
+    #[inline]
+    fn try_push_str(&mut self, s: &str) -> bool {
+        let len = self.len();
+        if s.len() > UTF8_CAPACITY - len {
+            return false;
+        }
+        let start = CODES_START + len;
+        self.bytes[start..(start + s.len())].copy_from_slice(s.as_bytes());
+        self.set_len(len + s.len());
+        true
+    }
+
+    /// Shortens the string to the given length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary.
+    #[inline]
+    fn truncate(&mut self, new_len: usize) {
+        let len = self.len();
+        if new_len >= len {
+            return;
+        }
+        assert!(self.as_str().is_char_boundary(new_len));
+        // Keep unused bytes zero.
+        for b in &mut self.bytes[(CODES_START + new_len)..(CODES_START + len)] {
+            *b = 0;
+        }
+        self.set_len(new_len);
+    }
+}
+
+/// Raw parts of a `String` on the heap.
+///
+/// The capacity is placed so that its most significant byte is the tag byte.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Heap {
+    /// Capacity.
+    #[cfg(target_endian = "big")]
+    cap: usize,
+    /// Pointer to the buffer.
+    ptr: NonNull<u8>,
+    /// Length.
+    len: usize,
+    /// Capacity.
+    #[cfg(target_endian = "little")]
+    cap: usize,
+}
+
+impl Heap {
+    /// Takes the raw parts of the string.
+    #[inline]
+    fn from_string(s: String) -> Self {
+        let mut s = ManuallyDrop::new(s);
+        let heap = Self {
+            ptr: NonNull::new(s.as_mut_ptr()).expect("Should never fail: `String` is non-null"),
+            len: s.len(),
+            cap: s.capacity(),
+        };
+        debug_assert!(heap.cap <= isize::MAX as usize);
+        heap
+    }
+
+    /// Returns the string slice.
+    #[inline]
+    fn as_str(&self) -> &str {
+        // SAFETY: The parts are taken from a live `String`.
+        unsafe { str::from_utf8_unchecked(slice::from_raw_parts(self.ptr.as_ptr(), self.len)) }
+    }
+
+    /// Rebuilds the string from the raw parts.
+    ///
+    /// # Safety
+    ///
+    /// The parts must not be used after this call unless they are replaced by
+    /// the parts of the returned string.
+    #[inline]
+    unsafe fn into_string(self) -> String {
+        String::from_raw_parts(self.ptr.as_ptr(), self.len, self.cap)
+    }
+}
+
+/// Internal representation of `PackedSmallString`.
+///
+/// The representation is inline if and only if the tag byte has
+/// [`INLINE_TAG`], and inline UTF-8 if it also has [`UTF8_TAG`].
+///
+/// [`INLINE_TAG`]: constant.INLINE_TAG.html
+/// [`UTF8_TAG`]: constant.UTF8_TAG.html
+#[repr(C)]
+union Repr {
+    /// Packed string.
+    packed: Packed,
+    /// UTF-8 string stored inline.
+    utf8: InlineUtf8,
+    /// String on the heap.
+    heap: Heap,
+}
+
+/// Mutable reference to the string on the heap.
+///
+/// The raw parts are written back on drop, even if the string is modified
+/// during a panic.
+struct HeapMut<'a> {
+    /// Raw parts.
+    heap: &'a mut Heap,
+    /// Rebuilt string.
+    string: ManuallyDrop<String>,
+}
+
+impl Drop for HeapMut<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        *self.heap = Heap::from_string(mem::take(&mut *self.string));
+    }
+}
+
+impl Deref for HeapMut<'_> {
+    type Target = String;
+
+    #[inline]
+    fn deref(&self) -> &String {
+        &self.string
+    }
+}
+
+impl DerefMut for HeapMut<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.string
+    }
+}
+
+/// String type which packs identifier-like strings.
+///
+/// Strings up to [`PACKED_CAPACITY`] bytes (30 bytes on 64-bit platforms)
+/// consisting of ASCII letters, digits, `-`, and `_` are stored in the packed
+/// representation. Other strings up to [`UTF8_CAPACITY`] bytes (23 bytes on
+/// 64-bit platforms) are stored inline as UTF-8, so short strings such as
+/// `"a b"` or `"caf\u{e9}"` need no allocations either. Longer strings are
+/// transparently stored on the heap.
+///
+/// The value is as large as `String`. A string on the heap is stored as the
+/// raw parts of `String`, and the inline representations use the most
+/// significant bit of the capacity as the tag, which is never set for heap
+/// capacities.
+///
+/// The representation is chosen on creation. A string which stops being packable
+/// is stored inline as UTF-8 if it fits, and moved to the heap otherwise.
+/// Strings on the heap stay there until [`shrink_to_fit`], which moves them
+/// inline again if possible.
+///
+/// [`PACKED_CAPACITY`]: constant.PACKED_CAPACITY.html
+/// [`UTF8_CAPACITY`]: constant.UTF8_CAPACITY.html
+/// [`shrink_to_fit`]: #method.shrink_to_fit
+pub struct PackedSmallString {
+    /// Representation.
+    repr: Repr,
+}
+
+// SAFETY: The heap buffer is uniquely owned as `String` is.
+unsafe impl Send for PackedSmallString {}
+
+// SAFETY: The heap buffer is never modified through shared references.
+unsafe impl Sync for PackedSmallString {}
+
+impl PackedSmallString {
+    /// Creates a new empty string.
+    #[inline]
+    pub fn new() -> Self {
+        Self::from_packed(Packed::EMPTY)
+    }
+
+    /// Creates a new string from the packed string.
+    #[inline]
+    fn from_packed(packed: Packed) -> Self {
+        Self {
+            repr: Repr { packed },
+        }
+    }
+
+    /// Creates a new string from the inline UTF-8 string.
+    #[inline]
+    fn from_inline_utf8(utf8: InlineUtf8) -> Self {
+        Self {
+            repr: Repr { utf8 },
+        }
+    }
+
+    /// Creates a new string on the heap.
+    #[inline]
+    fn from_heap(s: String) -> Self {
+        Self {
+            repr: Repr {
+                heap: Heap::from_string(s),
+            },
+        }
+    }
+
+    /// Creates a new inline string if the given string fits.
+    fn try_inline(s: &str) -> Option<Self> {
+        let mut packed = Packed::EMPTY;
+        if packed.try_push_str(s) {
+            return Some(Self::from_packed(packed));
+        }
+        let mut utf8 = InlineUtf8::EMPTY;
+        if utf8.try_push_str(s) {
+            return Some(Self::from_inline_utf8(utf8));
+        }
+        None
+    }
+
+    /// Returns the tag byte.
+    #[inline]
+    fn tag(&self) -> u8 {
+        // SAFETY: All the representations have all the bytes initialized.
+        unsafe { self.repr.packed.bytes[TAG_INDEX] }
+    }
+
+    /// Returns `true` if the string is stored in the packed representation.
+    #[inline]
+    pub fn is_packed(&self) -> bool {
+        self.tag() & (INLINE_TAG | UTF8_TAG) == INLINE_TAG
+    }
+
+    /// Returns `true` if the string is stored without heap allocations, either
+    /// packed or as UTF-8.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        self.tag() & INLINE_TAG != 0
+    }
+
+    /// Returns the packed string if the string is packed.
+    #[inline]
+    fn packed(&self) -> Option<&Packed> {
+        if self.is_packed() {
+            // SAFETY: The representation is packed.
+            Some(unsafe { &self.repr.packed })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the packed string if the string is packed.
+    #[inline]
+    fn packed_mut(&mut self) -> Option<&mut Packed> {
+        if self.is_packed() {
+            // SAFETY: The representation is packed.
+            Some(unsafe { &mut self.repr.packed })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the inline UTF-8 string if the string is stored so.
+    #[inline]
+    fn inline_utf8(&self) -> Option<&InlineUtf8> {
+        if self.is_inline() && !self.is_packed() {
+            // SAFETY: The representation is inline UTF-8.
+            Some(unsafe { &self.repr.utf8 })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the inline UTF-8 string if the string is stored so.
+    #[inline]
+    fn inline_utf8_mut(&mut self) -> Option<&mut InlineUtf8> {
+        if self.is_inline() && !self.is_packed() {
+            // SAFETY: The representation is inline UTF-8.
+            Some(unsafe { &mut self.repr.utf8 })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the raw parts of the string on the heap.
+    #[inline]
+    fn heap(&self) -> &Heap {
+        debug_assert!(!self.is_inline());
+        // SAFETY: The representation is not inline.
+        unsafe { &self.repr.heap }
+    }
+
+    /// Returns the string on the heap.
+    #[inline]
+    fn heap_mut(&mut self) -> HeapMut<'_> {
+        debug_assert!(!self.is_inline());
+        // SAFETY: The representation is not inline, and the parts are written
+        // back by `HeapMut`.
+        unsafe {
+            let heap = &mut self.repr.heap;
+            let string = ManuallyDrop::new(heap.into_string());
+            HeapMut { heap, string }
+        }
+    }
+
+    /// Returns the length in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        if let Some(packed) = self.packed() {
+            return packed.len();
+        }
+        match self.inline_utf8() {
+            Some(utf8) => utf8.len(),
+            None => self.heap().len,
+        }
+    }
+
+    /// Returns `true` if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a view of the string.
+    ///
+    /// Packed strings are decoded into the view.
+    #[inline]
+    pub fn as_str(&self) -> PackedStr<'_> {
+        let repr = match self.packed() {
+            Some(packed) => {
+                let mut buf = [0; PACKED_CAPACITY];
+                let len = packed.decode(&mut buf);
+                PackedStrRepr::Decoded {
+                    buf,
+                    len: len as u8,
+                }
+            }
+            None => match self.inline_utf8() {
+                Some(utf8) => PackedStrRepr::Borrowed(utf8.as_str()),
+                None => PackedStrRepr::Borrowed(self.heap().as_str()),
+            },
+        };
+        PackedStr { repr }
+    }
+
+    /// Moves the string to the heap, reserving space for `additional` more bytes.
+    fn make_heap(&mut self, additional: usize) -> HeapMut<'_> {
+        if self.is_inline() {
+            let view = self.as_str();
+            let mut s = String::with_capacity(view.len() + additional);
+            s.push_str(&view);
+            // Inline strings need no drop.
+            self.repr = Repr {
+                heap: Heap::from_string(s),
+            };
+        }
+        self.heap_mut()
+    }
+
+    /// Appends the given string.
+    ///
+    /// The string is stored inline as UTF-8 if the result cannot be packed,
+    /// and moved to the heap if it does not fit.
+    pub fn push_str(&mut self, s: &str) {
+        if let Some(packed) = self.packed_mut() {
+            if packed.try_push_str(s) {
+                return;
+            }
+        }
+        if let Some(utf8) = self.inline_utf8_mut() {
+            if utf8.try_push_str(s) {
+                return;
+            }
+        }
+        if self.is_packed() {
+            let mut utf8 = InlineUtf8::EMPTY;
+            if utf8.try_push_str(&self.as_str()) && utf8.try_push_str(s) {
+                *self = Self::from_inline_utf8(utf8);
+                return;
+            }
+        }
+        self.make_heap(s.len()).push_str(s);
+    }
+
+    /// Appends the given character.
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Shortens the string to the given length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary.
+    #[inline]
+    pub fn truncate(&mut self, new_len: usize) {
+        if let Some(packed) = self.packed_mut() {
+            return packed.truncate(new_len);
+        }
+        match self.inline_utf8_mut() {
+            Some(utf8) => utf8.truncate(new_len),
+            None => self.heap_mut().truncate(new_len),
+        }
+    }
+
+    /// Removes the last character and returns it.
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        match self.packed_mut() {
+            Some(packed) => {
+                let len = packed.len().checked_sub(1)?;
+                let ch = char::from(PACKED_ALPHABET[usize::from(packed.code(len))]);
+                packed.truncate(len);
+                Some(ch)
+            }
+            None => match self.inline_utf8_mut() {
+                Some(utf8) => {
+                    let ch = utf8.as_str().chars().next_back()?;
+                    utf8.truncate(utf8.len() - ch.len_utf8());
+                    Some(ch)
+                }
+                None => self.heap_mut().pop(),
+            },
+        }
+    }
+
+    /// Truncates the string to zero length.
+    ///
+    /// The string is moved to the packed representation.
+    #[inline]
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Shrinks the capacity to match the length.
+    ///
+    /// Strings are packed or moved inline if possible.
+    pub fn shrink_to_fit(&mut self) {
+        if self.is_packed() {
+            return;
+        }
+        let inline = Self::try_inline(&self.as_str());
+        match inline {
+            Some(inline) => *self = inline,
+            None => self.heap_mut().shrink_to_fit(),
+        }
+    }
+
+    /// Converts this string into a `SmallString`.
+    #[inline]
+    pub fn into_small_string(self) -> SmallString {
+        if self.is_inline() {
+            SmallString::from(&*self.as_str())
+        } else {
+            SmallString::from(self.into_string())
+        }
+    }
+
+    /// Converts this string into a `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        if self.is_inline() {
+            return String::from(&*self.as_str());
+        }
+        let this = ManuallyDrop::new(self);
+        // SAFETY: The representation is not inline, and `self` is not dropped.
+        unsafe { this.heap().into_string() }
+    }
+}
+
+impl Drop for PackedSmallString {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.is_inline() {
+            // SAFETY: The representation is not inline, and it is never used again.
+            drop(unsafe { self.heap().into_string() });
+        }
+    }
+}
+
+impl Clone for PackedSmallString {
+    #[inline]
+    fn clone(&self) -> Self {
+        if let Some(&packed) = self.packed() {
+            return Self::from_packed(packed);
+        }
+        match self.inline_utf8() {
+            Some(&utf8) => Self::from_inline_utf8(utf8),
+            None => Self::from_heap(String::from(self.heap().as_str())),
+        }
+    }
+}
+
+impl Default for PackedSmallString {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PackedSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.as_str(), f)
+    }
+}
+
+impl fmt::Display for PackedSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.as_str(), f)
+    }
+}
+
+impl fmt::Write for PackedSmallString {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl PartialEq for PackedSmallString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self.packed(), other.packed()) {
+            (Some(lhs), Some(rhs)) => lhs == rhs,
+            _ => *self.as_str() == *other.as_str(),
+        }
+    }
+}
+
+impl Eq for PackedSmallString {}
+
+impl PartialOrd for PackedSmallString {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PackedSmallString {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.packed(), other.packed()) {
+            // Codes are in the same order as the characters.
+            (Some(lhs), Some(rhs)) => (0..lhs.len().min(rhs.len()))
+                .map(|idx| lhs.code(idx).cmp(&rhs.code(idx)))
+                .find(|&ord| ord != Ordering::Equal)
+                .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+            _ => self.as_str().cmp(&other.as_str()),
+        }
+    }
+}
+
+impl Hash for PackedSmallString {
+    /// Hashes the string as `str` does.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+macro_rules! impl_cmp {
+    ($other:ty) => {
+        impl PartialEq<$other> for PackedSmallString {
+            #[inline]
+            fn eq(&self, other: &$other) -> bool {
+                PartialEq::eq(&*self.as_str(), &other[..])
+            }
+        }
+
+        impl PartialEq<PackedSmallString> for $other {
+            #[inline]
+            fn eq(&self, other: &PackedSmallString) -> bool {
+                PartialEq::eq(&self[..], &*other.as_str())
+            }
+        }
+    };
+}
+
+impl_cmp!(str);
+impl_cmp!(&str);
+impl_cmp!(String);
+impl_cmp!(SmallString);
+impl_cmp!(Cow<'_, str>);
+
+impl From<&str> for PackedSmallString {
+    fn from(s: &str) -> Self {
+        Self::try_inline(s).unwrap_or_else(|| Self::from_heap(String::from(s)))
+    }
+}
+
+impl From<SmallString> for PackedSmallString {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        match Self::try_inline(&s) {
+            Some(inline) => inline,
+            None => Self::from_heap(s.into_string()),
+        }
+    }
+}
+
+impl From<String> for PackedSmallString {
+    /// Stores the string inline if possible, or adopts the buffer otherwise.
+    fn from(s: String) -> Self {
+        match Self::try_inline(&s) {
+            Some(inline) => inline,
+            None => Self::from_heap(s),
+        }
+    }
+}
+
+impl From<PackedSmallString> for SmallString {
+    #[inline]
+    fn from(s: PackedSmallString) -> Self {
+        s.into_small_string()
+    }
+}
+
+impl From<PackedSmallString> for String {
+    #[inline]
+    fn from(s: PackedSmallString) -> Self {
+        s.into_string()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for PackedSmallString {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<char> for PackedSmallString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> Extend<&'a str> for PackedSmallString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|s| self.push_str(s));
+    }
+}
+
+impl Extend<char> for PackedSmallString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|ch| self.push(ch));
+    }
+}
+
+/// Internal representation of `PackedStr`.
+#[derive(Clone, Copy)]
+enum PackedStrRepr<'a> {
+    /// Borrowed string.
+    Borrowed(&'a str),
+    /// Decoded packed string.
+    Decoded {
+        /// Buffer.
+        buf: [u8; PACKED_CAPACITY],
+        /// Length.
+        len: u8,
+    },
+}
+
+/// View of `PackedSmallString` as a string slice.
+///
+/// Packed strings are decoded into the buffer in the view, so borrow it with
+/// `&*` or `Deref` rather than calling `as_str()` repeatedly.
+#[derive(Clone, Copy)]
+pub struct PackedStr<'a> {
+    /// Representation.
+    repr: PackedStrRepr<'a>,
+}
+
+impl Deref for PackedStr<'_> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        match &self.repr {
+            PackedStrRepr::Borrowed(s) => s,
+            // SAFETY: Decoded bytes are taken from `PACKED_ALPHABET`, which is ASCII.
+            PackedStrRepr::Decoded { buf, len } => unsafe {
+                str::from_utf8_unchecked(&buf[..usize::from(*len)])
+            },
+        }
+    }
+}
+
+impl AsRef<str> for PackedStr<'_> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Debug for PackedStr<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for PackedStr<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for PackedStr<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for PackedStr<'_> {}
+
+impl PartialOrd for PackedStr<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PackedStr<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl Hash for PackedStr<'_> {
+    /// Hashes the string as `str` does.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl PartialEq<str> for PackedStr<'_> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+impl PartialEq<&str> for PackedStr<'_> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_as_large_as_string() {
+        assert_eq!(
+            mem::size_of::<PackedSmallString>(),
+            mem::size_of::<String>()
+        );
+        // `SmallString` is larger with `inlinable_string` backend.
+        assert!(mem::size_of::<PackedSmallString>() <= mem::size_of::<SmallString>());
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        for (code, &b) in PACKED_ALPHABET.iter().enumerate() {
+            assert_eq!(encode(b), Some(code as u8));
+        }
+        assert_eq!(encode(b' '), None);
+        assert_eq!(encode(0x80), None);
+
+        let alphabet = str::from_utf8(PACKED_ALPHABET).unwrap();
+        for chunk in PACKED_ALPHABET.chunks(PACKED_CAPACITY) {
+            let chunk = str::from_utf8(chunk).unwrap();
+            let s = PackedSmallString::from(chunk);
+            assert!(s.is_packed());
+            assert_eq!(&*s.as_str(), chunk);
+        }
+        assert!(!is_packable(alphabet));
+        assert!(is_packable(&alphabet[..PACKED_CAPACITY]));
+    }
+
+    #[test]
+    fn codes_are_ordered_as_characters() {
+        let mut sorted = PACKED_ALPHABET.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(&sorted[..], &PACKED_ALPHABET[..]);
+
+        let strs = [
+            "", "-", "0", "A", "_", "a", "a-", "aa", "ab", "b", "z_z", "zz",
+        ];
+        for &lhs in &strs {
+            for &rhs in &strs {
+                let (packed_lhs, packed_rhs) =
+                    (PackedSmallString::from(lhs), PackedSmallString::from(rhs));
+                assert_eq!(
+                    packed_lhs.cmp(&packed_rhs),
+                    lhs.cmp(rhs),
+                    "{:?} cmp {:?}",
+                    lhs,
+                    rhs
+                );
+                assert_eq!(packed_lhs == packed_rhs, lhs == rhs);
+            }
+        }
+    }
+
+    #[test]
+    fn moves_between_representations() {
+        let full = &"main-content_2-abcdefghijklmnopqrstuvwxyz"[..PACKED_CAPACITY];
+        let mut s = PackedSmallString::from(full);
+        assert!(s.is_packed());
+        s.push('x');
+        assert!(!s.is_packed());
+        assert_eq!(s.pop(), Some('x'));
+        assert!(!s.is_packed());
+        assert_eq!(s, full);
+        s.shrink_to_fit();
+        assert!(s.is_packed());
+        assert_eq!(s, full);
+
+        let mut s = PackedSmallString::from("caf\u{e9}");
+        assert!(!s.is_packed());
+        assert!(s.is_inline());
+        s.truncate(3);
+        assert!(s.is_inline());
+        s.shrink_to_fit();
+        assert!(s.is_packed());
+        assert_eq!(s, "caf");
+
+        let mut s = PackedSmallString::from(String::from("a b"));
+        assert!(!s.is_packed());
+        assert!(s.is_inline());
+        s.clear();
+        assert!(s.is_packed());
+        assert!(s.is_empty());
+
+        let s = PackedSmallString::from("a b");
+        assert_eq!(s.clone().into_string(), "a b");
+        assert_eq!(s.into_small_string().as_str(), "a b");
+        assert_eq!(PackedSmallString::from("ab").into_string(), "ab");
+    }
+
+    #[test]
+    fn stores_short_strings_inline() {
+        let mut s = PackedSmallString::from("x");
+        s.push('.');
+        assert!(!s.is_packed());
+        assert!(s.is_inline());
+        assert_eq!(s, "x.");
+
+        let fits = "\u{3042}".repeat(UTF8_CAPACITY / 3);
+        let mut s = PackedSmallString::from(fits.as_str());
+        assert!(s.is_inline());
+        s.push_str(&" ".repeat(UTF8_CAPACITY - fits.len()));
+        assert!(s.is_inline());
+        assert_eq!(s.len(), UTF8_CAPACITY);
+        s.push(' ');
+        assert!(!s.is_inline());
+        assert_eq!(s.pop(), Some(' '));
+        assert!(!s.is_inline());
+        s.truncate(fits.len());
+        s.shrink_to_fit();
+        assert!(s.is_inline());
+        assert_eq!(s, fits.as_str());
+        assert_eq!(s.pop(), Some('\u{3042}'));
+        assert_eq!(s.len(), fits.len() - 3);
+
+        let long = "\u{e9}".repeat(UTF8_CAPACITY);
+        let s = PackedSmallString::from(long.as_str());
+        assert!(!s.is_inline());
+        assert_eq!(s.clone(), long.as_str());
+    }
+
+    #[test]
+    #[should_panic]
+    fn inline_truncate_panics_inside_char() {
+        PackedSmallString::from("\u{e9}").truncate(1);
+    }
+
+    /// Xorshift pseudo-random number generator.
+    struct Rng(u64);
+
+    impl Rng {
+        /// Returns a random number less than `n`.
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn random_operations_match_string() {
+        /// Characters to push. The last two ones cannot be packed.
+        const CHARS: &[char] = &['-', '0', '9', 'A', 'Z', '_', 'a', 'z', '\u{e9}', ' '];
+
+        /// Returns `true` if the string fits in one of the inline representations.
+        fn is_inline_len(s: &str) -> bool {
+            is_packable(s) || s.len() <= UTF8_CAPACITY
+        }
+
+        /// Asserts that the string is stored in the smallest representation.
+        #[track_caller]
+        fn assert_inline(s: &PackedSmallString, expected: &str) {
+            assert_eq!(s.is_packed(), is_packable(expected), "{:?}", expected);
+            assert_eq!(s.is_inline(), is_inline_len(expected), "{:?}", expected);
+        }
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..20000 {
+            let mut s = PackedSmallString::new();
+            let mut expected = String::new();
+            let mut saved = Vec::new();
+            for _ in 0..rng.below(60) {
+                match rng.below(10) {
+                    0..=4 => {
+                        let n = if rng.below(10) == 0 {
+                            CHARS.len()
+                        } else {
+                            CHARS.len() - 2
+                        };
+                        let ch = CHARS[rng.below(n)];
+                        let was_inline = s.is_inline();
+                        s.push(ch);
+                        expected.push(ch);
+                        // Inline strings move to the heap only when they outgrow it.
+                        assert_eq!(s.is_inline(), was_inline && is_inline_len(&expected));
+                    }
+                    5 => assert_eq!(s.pop(), expected.pop()),
+                    6 => {
+                        let mut new_len = rng.below(expected.len() + 1);
+                        while !expected.is_char_boundary(new_len) {
+                            new_len -= 1;
+                        }
+                        s.truncate(new_len);
+                        expected.truncate(new_len);
+                    }
+                    7 => {
+                        s.shrink_to_fit();
+                        assert_inline(&s, &expected);
+                    }
+                    8 => {
+                        s = PackedSmallString::from(expected.as_str());
+                        assert_inline(&s, &expected);
+                    }
+                    _ => saved.push((s.clone(), expected.clone())),
+                }
+                assert_eq!(s, expected.as_str());
+                assert_eq!(s.len(), expected.len());
+                assert!(!s.is_packed() || is_packable(&expected));
+                assert!(!s.is_inline() || is_inline_len(&expected));
+            }
+            for (saved, saved_expected) in &saved {
+                assert_eq!(saved.cmp(&s), saved_expected.cmp(&expected));
+                assert_eq!(*saved == s, *saved_expected == expected);
+            }
+        }
+    }
+}
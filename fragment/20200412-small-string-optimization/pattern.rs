@@ -0,0 +1,221 @@
+//! String pattern.
+
+use std::iter::FusedIterator;
+
+/// String pattern.
+///
+/// This is a stable subset of `std::str::pattern::Pattern`, implemented for
+/// `char`, `&str`, `&String`, `&[char]`, and `FnMut(char) -> bool`.
+pub trait Pattern {
+    /// Returns the byte range of the first match in the given string.
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)>;
+
+    /// Returns the byte range of the last match in the given string.
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)>;
+}
+
+/// Returns the byte range of the first character matching the given predicate.
+#[inline]
+fn find_char<F>(haystack: &str, mut f: F) -> Option<(usize, usize)>
+where
+    F: FnMut(char) -> bool,
+{
+    haystack
+        .char_indices()
+        .find(|&(_, ch)| f(ch))
+        .map(|(pos, ch)| (pos, pos + ch.len_utf8()))
+}
+
+/// Returns the byte range of the last character matching the given predicate.
+#[inline]
+fn rfind_char<F>(haystack: &str, mut f: F) -> Option<(usize, usize)>
+where
+    F: FnMut(char) -> bool,
+{
+    haystack
+        .char_indices()
+        .rev()
+        .find(|&(_, ch)| f(ch))
+        .map(|(pos, ch)| (pos, pos + ch.len_utf8()))
+}
+
+impl Pattern for char {
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack.find(*self).map(|pos| (pos, pos + self.len_utf8()))
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack
+            .rfind(*self)
+            .map(|pos| (pos, pos + self.len_utf8()))
+    }
+}
+
+impl Pattern for &'_ str {
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack.find(*self).map(|pos| (pos, pos + self.len()))
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack.rfind(*self).map(|pos| (pos, pos + self.len()))
+    }
+}
+
+impl Pattern for &'_ &'_ str {
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack.find(**self).map(|pos| (pos, pos + self.len()))
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack.rfind(**self).map(|pos| (pos, pos + self.len()))
+    }
+}
+
+impl Pattern for &'_ String {
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack
+            .find(self.as_str())
+            .map(|pos| (pos, pos + self.len()))
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        haystack
+            .rfind(self.as_str())
+            .map(|pos| (pos, pos + self.len()))
+    }
+}
+
+impl Pattern for &'_ [char] {
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        find_char(haystack, |ch| self.contains(&ch))
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        rfind_char(haystack, |ch| self.contains(&ch))
+    }
+}
+
+impl<F> Pattern for F
+where
+    F: FnMut(char) -> bool,
+{
+    #[inline]
+    fn find_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        find_char(haystack, self)
+    }
+
+    #[inline]
+    fn rfind_in(&mut self, haystack: &str) -> Option<(usize, usize)> {
+        rfind_char(haystack, self)
+    }
+}
+
+/// Iterator of byte ranges of non-overlapping matches of a pattern.
+///
+/// After an empty match, the next search starts after the following character
+/// in order to prevent matching at the same position again.
+#[derive(Debug, Clone)]
+pub(crate) struct Matches<'a, P> {
+    /// Source string.
+    haystack: &'a str,
+    /// Pattern.
+    pattern: P,
+    /// Position to start the next search, or `None` if there are no more matches.
+    search: Option<usize>,
+}
+
+impl<'a, P: Pattern> Matches<'a, P> {
+    /// Creates a new iterator.
+    #[inline]
+    pub(crate) fn new(haystack: &'a str, pattern: P) -> Self {
+        Self {
+            haystack,
+            pattern,
+            search: Some(0),
+        }
+    }
+}
+
+impl<P: Pattern> Iterator for Matches<'_, P> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let search = self.search?;
+        let found = self
+            .pattern
+            .find_in(&self.haystack[search..])
+            .map(|(start, end)| (search + start, search + end));
+        self.search = match found {
+            Some((start, end)) if start == end => self.haystack[end..]
+                .chars()
+                .next()
+                .map(|ch| end + ch.len_utf8()),
+            Some((_, end)) => Some(end),
+            None => None,
+        };
+
+        found
+    }
+}
+
+impl<P: Pattern> FusedIterator for Matches<'_, P> {}
+
+/// Iterator of substrings separated by a pattern.
+///
+/// This behaves as `std::str::Split`.
+#[derive(Debug, Clone)]
+pub struct Split<'a, P> {
+    /// Matches of the pattern.
+    matches: Matches<'a, P>,
+    /// Start position of the next substring.
+    start: usize,
+    /// Whether the last substring is already returned.
+    finished: bool,
+}
+
+impl<'a, P: Pattern> Split<'a, P> {
+    /// Creates a new iterator.
+    #[inline]
+    pub fn new(haystack: &'a str, pattern: P) -> Self {
+        Self {
+            matches: Matches::new(haystack, pattern),
+            start: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<'a, P: Pattern> Iterator for Split<'a, P> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let haystack = self.matches.haystack;
+        match self.matches.next() {
+            Some((start, end)) => {
+                let piece = &haystack[self.start..start];
+                self.start = end;
+                Some(piece)
+            }
+            None => {
+                self.finished = true;
+                Some(&haystack[self.start..])
+            }
+        }
+    }
+}
+
+impl<P: Pattern> FusedIterator for Split<'_, P> {}
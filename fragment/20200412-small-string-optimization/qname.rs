@@ -0,0 +1,494 @@
+//! XML qualified names and namespaces.
+
+use std::{
+    borrow::Borrow,
+    convert::TryFrom,
+    error, fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use crate::{
+    ident::{validate_xml_ncname, validate_xml_qname, IdentKind, InvalidIdentError, XmlName},
+    map::SmallStringMap,
+    string::{IntoSmallString, SmallString},
+};
+
+/// Namespace name bound to the `xml` prefix.
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace name bound to the `xmlns` prefix.
+pub const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Qualified name, `prefix:local` or `local`.
+///
+/// The whole name is stored in a single `SmallString`, so short names need no
+/// allocations.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QualifiedName {
+    /// Whole name.
+    name: SmallString,
+    /// Length of the prefix, or `0` if the name has no prefix.
+    ///
+    /// Prefixes are never empty, so `0` is unambiguous.
+    prefix_len: usize,
+}
+
+impl QualifiedName {
+    /// Creates a new name from the given validated name.
+    #[inline]
+    fn from_validated(name: SmallString) -> Self {
+        let prefix_len = name.find(':').unwrap_or(0);
+        Self { name, prefix_len }
+    }
+
+    /// Creates a new name, validating it as `QName`.
+    #[inline]
+    pub fn new(name: impl IntoSmallString) -> Result<Self, InvalidIdentError> {
+        let name = name.into_small_string();
+        validate_xml_qname(&name)?;
+        Ok(Self::from_validated(name))
+    }
+
+    /// Creates a new name from the prefix and the local name.
+    ///
+    /// Offsets in the error are the positions in `prefix:local_name`.
+    pub fn from_parts(prefix: Option<&str>, local_name: &str) -> Result<Self, InvalidIdentError> {
+        let kind = IdentKind::XmlQName;
+        let prefix_len = match prefix {
+            Some(prefix) => {
+                validate_xml_ncname(prefix, kind, 0)?;
+                validate_xml_ncname(local_name, kind, prefix.len() + 1)?;
+                prefix.len()
+            }
+            None => {
+                validate_xml_ncname(local_name, kind, 0)?;
+                0
+            }
+        };
+
+        let mut name = SmallString::with_capacity(prefix_len + 1 + local_name.len());
+        if let Some(prefix) = prefix {
+            name.push_str(prefix);
+            name.push(':');
+        }
+        name.push_str(local_name);
+        Ok(Self { name, prefix_len })
+    }
+
+    /// Returns the whole name.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the whole name.
+    #[inline]
+    pub fn into_small_string(self) -> SmallString {
+        self.name
+    }
+
+    /// Returns the prefix.
+    #[inline]
+    pub fn prefix(&self) -> Option<&str> {
+        if self.prefix_len == 0 {
+            None
+        } else {
+            Some(&self.name[..self.prefix_len])
+        }
+    }
+
+    /// Returns the local name.
+    #[inline]
+    pub fn local_name(&self) -> &str {
+        if self.prefix_len == 0 {
+            &self.name
+        } else {
+            &self.name[(self.prefix_len + 1)..]
+        }
+    }
+
+    /// Resolves the name of an element.
+    ///
+    /// Names without prefixes are in the default namespace.
+    #[inline]
+    pub fn resolve_element<'a>(
+        &'a self,
+        namespaces: &'a NamespaceMap,
+    ) -> Result<ExpandedName<'a>, UnboundPrefixError> {
+        self.resolve(namespaces, namespaces.default_namespace())
+    }
+
+    /// Resolves the name of an attribute.
+    ///
+    /// Names without prefixes are in no namespace.
+    #[inline]
+    pub fn resolve_attribute<'a>(
+        &'a self,
+        namespaces: &'a NamespaceMap,
+    ) -> Result<ExpandedName<'a>, UnboundPrefixError> {
+        self.resolve(namespaces, None)
+    }
+
+    /// Resolves the name, using the given namespace for names without prefixes.
+    fn resolve<'a>(
+        &'a self,
+        namespaces: &'a NamespaceMap,
+        unprefixed: Option<&'a str>,
+    ) -> Result<ExpandedName<'a>, UnboundPrefixError> {
+        let namespace = match self.prefix() {
+            Some(prefix) => match namespaces.namespace(prefix) {
+                Some(namespace) => Some(namespace),
+                None => {
+                    return Err(UnboundPrefixError {
+                        prefix: prefix.into(),
+                    })
+                }
+            },
+            None => unprefixed,
+        };
+        Ok(ExpandedName {
+            namespace,
+            local_name: self.local_name(),
+        })
+    }
+}
+
+impl Hash for QualifiedName {
+    /// Hashes the name as `str` does.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Display for QualifiedName {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for QualifiedName {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for QualifiedName {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<XmlName> for QualifiedName {
+    #[inline]
+    fn from(name: XmlName) -> Self {
+        Self::from_validated(name.into_small_string())
+    }
+}
+
+impl From<QualifiedName> for SmallString {
+    #[inline]
+    fn from(name: QualifiedName) -> Self {
+        name.into_small_string()
+    }
+}
+
+impl TryFrom<&str> for QualifiedName {
+    type Error = InvalidIdentError;
+
+    #[inline]
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+impl FromStr for QualifiedName {
+    type Err = InvalidIdentError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+/// Name resolved to the namespace name and the local name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExpandedName<'a> {
+    /// Namespace name.
+    namespace: Option<&'a str>,
+    /// Local name.
+    local_name: &'a str,
+}
+
+impl<'a> ExpandedName<'a> {
+    /// Creates a new name.
+    #[inline]
+    pub fn new(namespace: Option<&'a str>, local_name: &'a str) -> Self {
+        Self {
+            namespace,
+            local_name,
+        }
+    }
+
+    /// Returns the namespace name.
+    #[inline]
+    pub fn namespace(&self) -> Option<&'a str> {
+        self.namespace
+    }
+
+    /// Returns the local name.
+    #[inline]
+    pub fn local_name(&self) -> &'a str {
+        self.local_name
+    }
+}
+
+/// Error on resolving a name with an unbound prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnboundPrefixError {
+    /// Prefix.
+    prefix: SmallString,
+}
+
+impl UnboundPrefixError {
+    /// Returns the prefix.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl fmt::Display for UnboundPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "namespace prefix {:?} is not bound",
+            self.prefix.as_str()
+        )
+    }
+}
+
+impl error::Error for UnboundPrefixError {}
+
+/// Reason of binding error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BindReason {
+    /// The prefix is not a valid `NCName`.
+    InvalidPrefix(InvalidIdentError),
+    /// The prefix is `xml` or `xmlns`, which cannot be bound to other namespaces.
+    ReservedPrefix,
+    /// The namespace name is reserved for `xml` or `xmlns`.
+    ReservedNamespace,
+}
+
+/// Error on binding a namespace prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BindPrefixError {
+    /// Prefix.
+    prefix: SmallString,
+    /// Reason.
+    reason: BindReason,
+}
+
+impl BindPrefixError {
+    /// Returns the prefix.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the validation error if the prefix is not a valid `NCName`.
+    #[inline]
+    pub fn invalid_ident_error(&self) -> Option<&InvalidIdentError> {
+        match &self.reason {
+            BindReason::InvalidPrefix(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BindPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot bind namespace prefix {:?}: ",
+            self.prefix.as_str()
+        )?;
+        match &self.reason {
+            BindReason::InvalidPrefix(e) => e.fmt(f),
+            BindReason::ReservedPrefix => f.write_str("the prefix is reserved"),
+            BindReason::ReservedNamespace => f.write_str("the namespace name is reserved"),
+        }
+    }
+}
+
+impl error::Error for BindPrefixError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.reason {
+            BindReason::InvalidPrefix(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Bindings of namespace prefixes.
+///
+/// The `xml` prefix is always bound to [`XML_NAMESPACE`], and the `xmlns` prefix is always
+/// bound to [`XMLNS_NAMESPACE`].
+///
+/// [`XML_NAMESPACE`]: constant.XML_NAMESPACE.html
+/// [`XMLNS_NAMESPACE`]: constant.XMLNS_NAMESPACE.html
+#[derive(Debug, Clone, Default)]
+pub struct NamespaceMap {
+    /// Namespace names for prefixes.
+    prefixes: SmallStringMap<SmallString>,
+    /// Default namespace name.
+    default: Option<SmallString>,
+}
+
+impl NamespaceMap {
+    /// Creates a new map without bindings.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the prefix to the namespace name, and returns the old one.
+    ///
+    /// As the Namespaces in XML specification requires, the prefix must be a valid `NCName`,
+    /// `xmlns` cannot be bound, `xml` can only be bound to [`XML_NAMESPACE`], and no other
+    /// prefixes can be bound to [`XML_NAMESPACE`] or [`XMLNS_NAMESPACE`].
+    /// Binding `xml` to [`XML_NAMESPACE`] is allowed but does nothing.
+    ///
+    /// [`XML_NAMESPACE`]: constant.XML_NAMESPACE.html
+    /// [`XMLNS_NAMESPACE`]: constant.XMLNS_NAMESPACE.html
+    pub fn bind(
+        &mut self,
+        prefix: impl IntoSmallString,
+        namespace: impl IntoSmallString,
+    ) -> Result<Option<SmallString>, BindPrefixError> {
+        let prefix = prefix.into_small_string();
+        let namespace = namespace.into_small_string();
+        let reason = match (prefix.as_str(), namespace.as_str()) {
+            ("xml", XML_NAMESPACE) => return Ok(None),
+            ("xml", _) | ("xmlns", _) => Some(BindReason::ReservedPrefix),
+            (_, XML_NAMESPACE) | (_, XMLNS_NAMESPACE) => Some(BindReason::ReservedNamespace),
+            (prefix, _) => validate_xml_ncname(prefix, IdentKind::XmlNcName, 0)
+                .err()
+                .map(BindReason::InvalidPrefix),
+        };
+        match reason {
+            Some(reason) => Err(BindPrefixError { prefix, reason }),
+            None => Ok(self.prefixes.insert(prefix, namespace)),
+        }
+    }
+
+    /// Removes the binding of the prefix, and returns the namespace name.
+    #[inline]
+    pub fn unbind(&mut self, prefix: &str) -> Option<SmallString> {
+        self.prefixes.remove(prefix)
+    }
+
+    /// Sets the default namespace name, and returns the old one.
+    #[inline]
+    pub fn set_default_namespace(
+        &mut self,
+        namespace: impl IntoSmallString,
+    ) -> Option<SmallString> {
+        self.default.replace(namespace.into_small_string())
+    }
+
+    /// Clears the default namespace name, and returns the old one.
+    #[inline]
+    pub fn clear_default_namespace(&mut self) -> Option<SmallString> {
+        self.default.take()
+    }
+
+    /// Returns the default namespace name.
+    #[inline]
+    pub fn default_namespace(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Returns the namespace name bound to the prefix.
+    #[inline]
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        match prefix {
+            "xml" => Some(XML_NAMESPACE),
+            "xmlns" => Some(XMLNS_NAMESPACE),
+            prefix => self.prefixes.get(prefix).map(SmallString::as_str),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_and_resolve() {
+        let mut namespaces = NamespaceMap::new();
+        assert_eq!(
+            namespaces.bind("svg", "http://www.w3.org/2000/svg"),
+            Ok(None)
+        );
+        let name = QualifiedName::new("svg:rect").unwrap();
+        let expanded = name.resolve_element(&namespaces).unwrap();
+        assert_eq!(expanded.namespace(), Some("http://www.w3.org/2000/svg"));
+        assert_eq!(expanded.local_name(), "rect");
+        assert_eq!(
+            namespaces.namespace("xmlns"),
+            Some(XMLNS_NAMESPACE),
+            "`xmlns` is predefined"
+        );
+    }
+
+    #[test]
+    fn bind_rejects_invalid_prefix() {
+        let mut namespaces = NamespaceMap::new();
+        for &prefix in &["", "1a", "a:b", "a b"] {
+            let e = namespaces.bind(prefix, "urn:example").unwrap_err();
+            assert_eq!(e.prefix(), prefix);
+            assert!(e.invalid_ident_error().is_some(), "{:?}", prefix);
+        }
+        assert_eq!(namespaces.namespace("a:b"), None);
+    }
+
+    #[test]
+    fn bind_rejects_reserved_names() {
+        let mut namespaces = NamespaceMap::new();
+        assert_eq!(namespaces.bind("xml", XML_NAMESPACE), Ok(None));
+        assert!(namespaces.bind("xml", "urn:example").is_err());
+        assert!(namespaces.bind("xmlns", XMLNS_NAMESPACE).is_err());
+        assert!(namespaces.bind("xmlns", "urn:example").is_err());
+        assert!(namespaces.bind("x", XML_NAMESPACE).is_err());
+        assert!(namespaces.bind("x", XMLNS_NAMESPACE).is_err());
+        assert_eq!(namespaces.namespace("xml"), Some(XML_NAMESPACE));
+        assert_eq!(namespaces.namespace("xmlns"), Some(XMLNS_NAMESPACE));
+        assert_eq!(namespaces.namespace("x"), None);
+    }
+
+    #[test]
+    fn default_namespace_applies_to_elements_only() {
+        let mut namespaces = NamespaceMap::new();
+        let name = QualifiedName::new("html").unwrap();
+        assert_eq!(
+            namespaces.set_default_namespace("http://www.w3.org/1999/xhtml"),
+            None
+        );
+        let element = name.resolve_element(&namespaces).unwrap();
+        assert_eq!(element.namespace(), Some("http://www.w3.org/1999/xhtml"));
+        let attribute = name.resolve_attribute(&namespaces).unwrap();
+        assert_eq!(attribute.namespace(), None);
+
+        let old = namespaces.clear_default_namespace();
+        assert_eq!(old.as_deref(), Some("http://www.w3.org/1999/xhtml"));
+        assert_eq!(namespaces.default_namespace(), None);
+        assert_eq!(name.resolve_element(&namespaces).unwrap().namespace(), None);
+    }
+}
@@ -0,0 +1,344 @@
+//! Slugs and unique ids.
+
+use crate::{
+    map::{Entry, SmallStringMap},
+    string::{IntoSmallString, SmallString},
+};
+
+/// Upper-case hexadecimal digits for percent-encoding.
+const PERCENT_HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Handling of non-ASCII letters and digits in slugs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NonAscii {
+    /// Keeps them as is.
+    Keep,
+    /// Transliterates Latin letters with diacritics into ASCII, and
+    /// percent-encodes other characters.
+    Transliterate,
+    /// Percent-encodes them as UTF-8.
+    PercentEncode,
+}
+
+/// Options for slug generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SlugOptions {
+    /// Whether to lowercase letters.
+    lowercase: bool,
+    /// Separator of words.
+    separator: char,
+    /// Handling of non-ASCII letters and digits.
+    non_ascii: NonAscii,
+    /// Maximum length in bytes.
+    max_len: Option<usize>,
+}
+
+impl SlugOptions {
+    /// Creates the default options.
+    ///
+    /// By default, letters are lowercased, words are separated by `-`,
+    /// non-ASCII characters are kept, and the length is not limited.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to lowercase letters.
+    #[inline]
+    pub fn lowercase(self, lowercase: bool) -> Self {
+        Self { lowercase, ..self }
+    }
+
+    /// Sets the separator of words.
+    #[inline]
+    pub fn separator(self, separator: char) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// Sets how to handle non-ASCII letters and digits.
+    #[inline]
+    pub fn non_ascii(self, non_ascii: NonAscii) -> Self {
+        Self { non_ascii, ..self }
+    }
+
+    /// Sets the maximum length in bytes.
+    ///
+    /// The slug is truncated before the first character which does not fit.
+    /// A character is never split, so all the percent-encoded bytes of a
+    /// character are kept or dropped together, and no separator is left at the end.
+    #[inline]
+    pub fn max_len(self, max_len: Option<usize>) -> Self {
+        Self { max_len, ..self }
+    }
+}
+
+impl Default for SlugOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            separator: '-',
+            non_ascii: NonAscii::Keep,
+            max_len: None,
+        }
+    }
+}
+
+/// Returns ASCII transliteration of the given Latin letter.
+fn transliterate(c: char) -> Option<&'static str> {
+    let s = match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ð' | 'Ď' | 'Đ' => "D",
+        'ð' | 'ď' | 'đ' => "d",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĳ' => "IJ",
+        'ĳ' => "ij",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' | 'ĸ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' | 'Ŋ' => "N",
+        'ñ' | 'ń' | 'ņ' | 'ň' | 'ŉ' | 'ŋ' => "n",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ſ' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Þ' => "TH",
+        'þ' => "th",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ý' | 'Ŷ' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(s)
+}
+
+/// Appends the percent-encoded character.
+fn push_percent_encoded(dest: &mut SmallString, c: char) {
+    let mut buf = [0; 4];
+    for &b in c.encode_utf8(&mut buf).as_bytes() {
+        dest.push('%');
+        dest.push(char::from(PERCENT_HEX_DIGITS[usize::from(b >> 4)]));
+        dest.push(char::from(PERCENT_HEX_DIGITS[usize::from(b & 0xf)]));
+    }
+}
+
+/// Appends the ASCII string, lowercasing it if necessary.
+#[inline]
+fn push_ascii(dest: &mut SmallString, s: &str, lowercase: bool) {
+    if lowercase {
+        dest.extend(s.chars().map(|c| c.to_ascii_lowercase()));
+    } else {
+        dest.push_str(s);
+    }
+}
+
+/// Appends the letter or digit to the slug.
+fn push_alphanumeric(dest: &mut SmallString, c: char, options: &SlugOptions) {
+    if c.is_ascii() {
+        let mut buf = [0; 4];
+        push_ascii(dest, c.encode_utf8(&mut buf), options.lowercase);
+        return;
+    }
+
+    let c = match options.non_ascii {
+        NonAscii::Keep => {
+            if options.lowercase {
+                dest.extend(c.to_lowercase());
+            } else {
+                dest.push(c);
+            }
+            return;
+        }
+        NonAscii::Transliterate => match transliterate(c) {
+            Some(s) => return push_ascii(dest, s, options.lowercase),
+            None => c,
+        },
+        NonAscii::PercentEncode => c,
+    };
+    if options.lowercase {
+        c.to_lowercase().for_each(|c| push_percent_encoded(dest, c));
+    } else {
+        push_percent_encoded(dest, c);
+    }
+}
+
+/// Appends the slug of the given string to `dest`.
+///
+/// Letters and digits are kept, and the other characters separate words.
+pub(crate) fn push_slug(dest: &mut SmallString, s: &str, options: &SlugOptions) {
+    let start = dest.len();
+    let max_end = options
+        .max_len
+        .map_or(usize::MAX, |max_len| start.saturating_add(max_len));
+    let mut separated = false;
+
+    for c in s.chars() {
+        if !c.is_alphanumeric() {
+            separated = true;
+            continue;
+        }
+
+        let prev_len = dest.len();
+        if separated && prev_len > start {
+            dest.push(options.separator);
+        }
+        separated = false;
+        push_alphanumeric(dest, c, options);
+        if dest.len() > max_end {
+            dest.truncate(prev_len);
+            break;
+        }
+    }
+}
+
+/// Registry of ids, which generates unique ids.
+///
+/// If an id is already taken, the following ids are `{id}-1`, `{id}-2`, and so on.
+#[derive(Debug, Clone)]
+pub struct IdRegistry {
+    /// Taken ids, and the last suffix number used for them.
+    ids: SmallStringMap<usize>,
+    /// Separator between ids and suffix numbers.
+    separator: char,
+}
+
+impl IdRegistry {
+    /// Creates a new empty registry.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the separator between ids and suffix numbers.
+    #[inline]
+    pub fn separator(self, separator: char) -> Self {
+        Self { separator, ..self }
+    }
+
+    /// Returns the number of taken ids.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if no ids are taken.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns `true` if the id is taken.
+    #[inline]
+    pub fn is_taken(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// Marks the id as taken, e.g. for ids explicitly specified by users.
+    ///
+    /// Returns `false` if the id is already taken.
+    #[inline]
+    pub fn reserve(&mut self, id: impl IntoSmallString) -> bool {
+        match self.ids.entry(id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(0);
+                true
+            }
+        }
+    }
+
+    /// Returns a unique id based on the given one, and marks it as taken.
+    ///
+    /// An empty id is replaced with `_`.
+    pub fn unique_id(&mut self, base: &str) -> SmallString {
+        let base = if base.is_empty() { "_" } else { base };
+        let mut suffix = match self.ids.get(base) {
+            Some(&suffix) => suffix,
+            None => {
+                self.ids.insert(base, 0);
+                return base.into();
+            }
+        };
+
+        let mut id = SmallString::with_capacity(base.len() + 4);
+        loop {
+            suffix += 1;
+            id.clear();
+            id.push_str(base);
+            id.push(self.separator);
+            id.push_dec(suffix);
+            if !self.ids.contains_key(&id) {
+                break;
+            }
+        }
+        self.ids[base] = suffix;
+        self.ids.insert(id.clone(), 0);
+        id
+    }
+
+    /// Returns a unique id based on the slug of the given string, and marks it as taken.
+    ///
+    /// Note that the suffix number may exceed `max_len` of the options.
+    #[inline]
+    pub fn unique_slug(&mut self, s: &str, options: SlugOptions) -> SmallString {
+        self.unique_id(&SmallString::slugify(s, options))
+    }
+
+    /// Returns an iterator of the taken ids.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator + '_ {
+        self.ids.keys()
+    }
+}
+
+impl Default for IdRegistry {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            ids: SmallStringMap::new(),
+            separator: '-',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_len_keeps_percent_encoded_character() {
+        let options = SlugOptions::new()
+            .non_ascii(NonAscii::PercentEncode)
+            .max_len(Some(10));
+        // "あ" is "%E3%81%82", which does not fit after "ab-".
+        assert_eq!(SmallString::slugify("ab あ", options).as_str(), "ab");
+        assert_eq!(SmallString::slugify("あ b", options).as_str(), "%E3%81%82");
+    }
+}
@@ -1,6 +1,35 @@
 //! String type.
 
-use std::{fmt, string::FromUtf8Error};
+use std::{
+    borrow::{Borrow, BorrowMut, Cow},
+    cmp::Ordering,
+    convert::{Infallible, TryFrom},
+    ffi::{CStr, CString, NulError, OsStr, OsString},
+    fmt,
+    hash::{Hash, Hasher},
+    iter::{FromIterator, FusedIterator},
+    mem,
+    ops::{
+        Add, AddAssign, Deref, DerefMut, Index, IndexMut, Range, RangeBounds, RangeFrom, RangeFull,
+        RangeInclusive, RangeTo, RangeToInclusive,
+    },
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::{FromStr, Utf8Error},
+    sync::Arc,
+};
+
+use crate::{
+    cow::SmallCow,
+    error::SmallFromUtf8Error,
+    format::{Digits, Integer},
+    io::LineEnding,
+    pattern::{Matches, Pattern},
+    slug::SlugOptions,
+};
+
+#[cfg(feature = "small-string")]
+use std::{marker::PhantomData, ops::Bound};
 
 #[cfg(feature = "small-string")]
 use inlinable_string::{InlinableString, StringExt, INLINE_STRING_CAPACITY};
@@ -13,11 +42,64 @@ type BackendString = InlinableString;
 #[cfg(not(feature = "small-string"))]
 type BackendString = String;
 
+/// Maximum length of strings which can be inlined.
+#[cfg(feature = "small-string")]
+pub(crate) const INLINE_CAPACITY: usize = INLINE_STRING_CAPACITY;
+
+/// Maximum length of strings regarded as short enough to be inlined.
+#[cfg(not(feature = "small-string"))]
+pub(crate) const INLINE_CAPACITY: usize = std::mem::size_of::<String>() - 1;
+
+/// Size of the stack buffer used to create a C string without allocation.
+const C_STR_STACK_BUFFER_LEN: usize = 128;
+
+/// Policy on whether to adopt the buffer of a `String` on conversion.
+///
+/// Without small string optimization, inlining means copying to a newly
+/// allocated buffer of the exact size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlinePolicy {
+    /// Always adopts the heap buffer.
+    AlwaysAdopt,
+    /// Inlines the string if it fits.
+    InlineWhenFits,
+    /// Inlines the string if it fits and the buffer has much excess capacity.
+    InlineWhenWasteful,
+}
+
+impl InlinePolicy {
+    /// Returns `true` if a string with the given length and capacity should be inlined.
+    #[inline]
+    fn should_inline(self, len: usize, capacity: usize) -> bool {
+        match self {
+            InlinePolicy::AlwaysAdopt => false,
+            InlinePolicy::InlineWhenFits => len <= INLINE_CAPACITY,
+            InlinePolicy::InlineWhenWasteful => len <= INLINE_CAPACITY && capacity / 2 > len,
+        }
+    }
+}
+
+impl Default for InlinePolicy {
+    /// Inlining is free with small string optimization.
+    #[inline]
+    #[cfg(feature = "small-string")]
+    fn default() -> Self {
+        InlinePolicy::InlineWhenFits
+    }
+
+    /// Inlining requires an allocation without small string optimization.
+    #[inline]
+    #[cfg(not(feature = "small-string"))]
+    fn default() -> Self {
+        InlinePolicy::InlineWhenWasteful
+    }
+}
+
 /// Owned string type with optional small string optimization.
 ///
 /// This type is intended to be used to contain strings which is expected to be very short.
 /// However, this type can contain long string.
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default, Debug, Clone)]
 pub struct SmallString {
     /// Inner string.
     inner: BackendString,
@@ -42,10 +124,106 @@ impl SmallString {
         Self::from_inner(BackendString::with_capacity(capacity))
     }
 
+    /// Creates a new string from the given `String`, following the given policy.
+    #[inline]
+    #[cfg(not(feature = "small-string"))]
+    pub fn from_string_with(policy: InlinePolicy, s: String) -> Self {
+        if policy.should_inline(s.len(), s.capacity()) {
+            Self::from(s.as_str())
+        } else {
+            Self::from_inner(s)
+        }
+    }
+
+    /// Creates a new string from the given `String`, following the given policy.
+    #[inline]
+    #[cfg(feature = "small-string")]
+    pub fn from_string_with(policy: InlinePolicy, s: String) -> Self {
+        if policy.should_inline(s.len(), s.capacity()) {
+            Self::from(s.as_str())
+        } else {
+            Self::from_inner(InlinableString::Heap(s))
+        }
+    }
+
+    /// Creates a new string from the decimal representation of the integer.
+    ///
+    /// This is faster than formatting by `std::fmt`.
+    #[inline]
+    pub fn from_dec<I: Integer>(n: I) -> Self {
+        let digits = n.to_dec_digits();
+        Self::from(digits.as_str())
+    }
+
+    /// Creates a new string from the lower-case hexadecimal representation of the integer.
+    ///
+    /// This is faster than formatting by `std::fmt`.
+    #[inline]
+    pub fn from_hex<I: Integer>(n: I) -> Self {
+        let digits = n.to_hex_digits();
+        Self::from(digits.as_str())
+    }
+
     /// Converts a vector of byets to a string.
     #[inline]
-    pub fn from_utf8(vec: Vec<u8>) -> Result<Self, FromUtf8Error> {
-        BackendString::from_utf8(vec).map(Self::from_inner)
+    pub fn from_utf8(vec: Vec<u8>) -> Result<Self, SmallFromUtf8Error> {
+        BackendString::from_utf8(vec)
+            .map(Self::from_inner)
+            .map_err(Into::into)
+    }
+
+    /// Converts a slice of bytes to a string.
+    ///
+    /// The content is copied into the inline buffer if it fits.
+    ///
+    /// On error, the bytes are not copied, since the caller still has them.
+    #[inline]
+    pub fn from_utf8_slice(v: &[u8]) -> Result<Self, Utf8Error> {
+        std::str::from_utf8(v).map(Self::from)
+    }
+
+    /// Converts a slice of bytes to a string without checking that the string
+    /// contains valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// `v` should be valid UTF-8 sequence.
+    #[inline]
+    pub unsafe fn from_utf8_unchecked_slice(v: &[u8]) -> Self {
+        Self::from(std::str::from_utf8_unchecked(v))
+    }
+
+    /// Converts a slice of bytes to a string, including invalid characters.
+    ///
+    /// Invalid UTF-8 sequences are replaced with U+FFFD REPLACEMENT CHARACTER.
+    /// Returns the borrowed string if `v` is valid UTF-8.
+    ///
+    /// Unlike `String::from_utf8_lossy()`, the result is built directly in a
+    /// `SmallString`, so short results are stored inline.
+    pub fn from_utf8_lossy(v: &[u8]) -> SmallCow<'_> {
+        let mut error = match std::str::from_utf8(v) {
+            Ok(s) => return SmallCow::Borrowed(s),
+            Err(e) => e,
+        };
+
+        let mut lossy = Self::with_capacity(v.len());
+        let mut rest = v;
+        loop {
+            let (valid, invalid) = rest.split_at(error.valid_up_to());
+            lossy.push_str(
+                std::str::from_utf8(valid).expect("Should never fail: already validated"),
+            );
+            lossy.push(char::REPLACEMENT_CHARACTER);
+            // An incomplete sequence at the end is replaced at once.
+            rest = &invalid[error.error_len().unwrap_or(invalid.len())..];
+            match std::str::from_utf8(rest) {
+                Ok(s) => {
+                    lossy.push_str(s);
+                    return SmallCow::Owned(lossy);
+                }
+                Err(e) => error = e,
+            }
+        }
     }
 
     /// Converts a stringng into a byte vector.
@@ -72,6 +250,45 @@ impl SmallString {
         self.inner.push_str(string)
     }
 
+    /// Appends the decimal representation of the integer.
+    #[inline]
+    pub fn push_dec<I: Integer>(&mut self, n: I) {
+        self.push_str(n.to_dec_digits().as_str())
+    }
+
+    /// Appends the decimal representation of the integer, padded with zeros to `width`.
+    ///
+    /// This is same as formatting with `{:0width$}`: the sign is counted as a part of the width.
+    #[inline]
+    pub fn push_dec_padded<I: Integer>(&mut self, n: I, width: usize) {
+        self.push_digits_padded(n.to_dec_digits(), width)
+    }
+
+    /// Appends the lower-case hexadecimal representation of the integer.
+    #[inline]
+    pub fn push_hex<I: Integer>(&mut self, n: I) {
+        self.push_str(n.to_hex_digits().as_str())
+    }
+
+    /// Appends the lower-case hexadecimal representation of the integer,
+    /// padded with zeros to `width`.
+    #[inline]
+    pub fn push_hex_padded<I: Integer>(&mut self, n: I, width: usize) {
+        self.push_digits_padded(n.to_hex_digits(), width)
+    }
+
+    /// Appends the formatted integer, padded with zeros to `width`.
+    fn push_digits_padded(&mut self, digits: Digits, width: usize) {
+        let (sign, digits) = digits.sign_and_digits();
+        let padding = width.saturating_sub(sign.len() + digits.len());
+        self.reserve(sign.len() + padding + digits.len());
+        self.push_str(sign);
+        for _ in 0..padding {
+            self.push('0');
+        }
+        self.push_str(digits);
+    }
+
     /// Returns this string's capacity, in bytes.
     #[inline]
     pub fn capacity(&self) -> usize {
@@ -90,6 +307,14 @@ impl SmallString {
         self.inner.reserve_exact(additional)
     }
 
+    /// Shrinks the capacity of this string to match its length.
+    ///
+    /// Short heap string is moved to the inline buffer.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit()
+    }
+
     /// Appends the given `char` to the end of this string.
     #[inline]
     pub fn push(&mut self, ch: char) {
@@ -192,7 +417,201 @@ impl SmallString {
         self.inner.clear()
     }
 
+    /// Removes the specified range in the string, and replaces it with the given string.
+    #[inline]
+    #[cfg(not(feature = "small-string"))]
+    pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.inner.replace_range(range, replace_with)
+    }
+
+    /// Removes the specified range in the string, and replaces it with the given string.
+    #[inline]
+    #[cfg(feature = "small-string")]
+    pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = resolve_range(range, self.len());
+        replace_range(&mut self.inner, range, replace_with)
+    }
+
+    /// Removes the specified range from the string, and returns the removed characters.
+    #[inline]
+    #[cfg(not(feature = "small-string"))]
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        Drain {
+            inner: self.inner.drain(range),
+        }
+    }
+
+    /// Removes the specified range from the string, and returns the removed characters.
+    ///
+    /// The range is removed immediately, even if the iterator is not consumed.
+    #[inline]
+    #[cfg(feature = "small-string")]
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = resolve_range(range, self.len());
+        let removed = InlinableString::from(&self.inner[range.clone()]);
+        replace_range(&mut self.inner, range, "");
+
+        Drain {
+            back: removed.len(),
+            removed,
+            front: 0,
+            _string: PhantomData,
+        }
+    }
+
+    /// Replaces all matches of a pattern with another string, and returns the result.
+    ///
+    /// Unlike `str::replace()`, this returns a `SmallString`.
+    #[inline]
+    pub fn replace<P: Pattern>(&self, from: P, to: &str) -> Self {
+        self.replacen(from, to, usize::MAX)
+    }
+
+    /// Replaces first `count` matches of a pattern with another string, and returns the result.
+    ///
+    /// Unlike `str::replacen()`, this returns a `SmallString`.
+    /// The result is inlined if it fits.
+    #[inline]
+    pub fn replacen<P: Pattern>(&self, from: P, to: &str, count: usize) -> Self {
+        replaced(self, Matches::new(self, from).take(count), to)
+    }
+
+    /// Replaces all matches of a pattern with another string.
+    ///
+    /// If the pattern does not match, the string is not modified and no allocation happens.
+    #[inline]
+    pub fn replace_in_place<P: Pattern>(&mut self, from: P, to: &str) {
+        self.replacen_in_place(from, to, usize::MAX)
+    }
+
+    /// Replaces first `count` matches of a pattern with another string.
+    ///
+    /// If the pattern does not match, the string is not modified and no allocation happens.
+    /// The result is inlined if it fits.
+    pub fn replacen_in_place<P: Pattern>(&mut self, from: P, to: &str, count: usize) {
+        let mut matches = Matches::new(self, from).take(count).peekable();
+        if matches.peek().is_none() {
+            return;
+        }
+
+        *self = replaced(self, matches, to);
+    }
+
+    /// Calls the given function with the NUL-terminated copy of the string.
+    ///
+    /// If the string is shorter than 128 bytes, it is NUL-terminated in a
+    /// buffer on the stack, so no allocation happens.
+    /// Otherwise, a `CString` is allocated.
+    ///
+    /// The inline buffer itself is not used, since it cannot be modified through
+    /// `&self` and its bytes after the content are not guaranteed to be zero.
+    /// Copying an inline string to the stack is as cheap as copying the inline
+    /// buffer, and the stack buffer also serves short heap strings.
+    ///
+    /// Returns an error if the string contains a NUL byte.
+    pub fn with_c_str<F, R>(&self, f: F) -> Result<R, NulError>
+    where
+        F: FnOnce(&CStr) -> R,
+    {
+        let bytes = self.as_bytes();
+        if bytes.len() >= C_STR_STACK_BUFFER_LEN {
+            return CString::new(bytes).map(|s| f(&s));
+        }
+
+        let mut buf = [0_u8; C_STR_STACK_BUFFER_LEN];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        match CStr::from_bytes_with_nul(&buf[..=bytes.len()]) {
+            Ok(s) => Ok(f(s)),
+            // The error is created by `CString::new()`, since `NulError` has no public constructor.
+            // This allocates, but only on error.
+            Err(_) => Err(CString::new(bytes)
+                .expect_err("Should never fail: the string has an interior NUL byte")),
+        }
+    }
+
+    /// Reads a line from the reader, and appends it to this `SmallString`.
+    ///
+    /// The bytes are validated as UTF-8 directly from the buffer of the reader.
+    /// Clear the string before calling this in order to reuse the buffer for multiple lines.
+    ///
+    /// Returns the number of bytes consumed, including the line ending.
+    /// `Ok(0)` means the reader has reached EOF.
+    /// On error, the string is restored to its original content.
+    #[inline]
+    pub fn read_line_from<R: std::io::BufRead + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        line_ending: LineEnding,
+    ) -> std::io::Result<usize> {
+        crate::io::read_line(self, reader, line_ending)
+    }
+
+    /// Creates a slug of the given string, e.g. for anchor ids from section titles.
+    ///
+    /// Letters and digits are kept, and runs of the other characters become a separator.
+    #[inline]
+    pub fn slugify(s: &str, options: SlugOptions) -> Self {
+        let mut slug = Self::new();
+        slug.push_slug(s, options);
+        slug
+    }
+
+    /// Appends the slug of the given string.
+    ///
+    /// `max_len` of the options limits the length of the appended part.
+    #[inline]
+    pub fn push_slug(&mut self, s: &str, options: SlugOptions) {
+        crate::slug::push_slug(self, s, &options)
+    }
+
+    /// Takes the string out, leaving an empty string in its place.
+    ///
+    /// This never allocates nor copies the content.
+    #[inline]
+    pub fn take(&mut self) -> Self {
+        mem::take(self)
+    }
+
+    /// Converts this string into a `String`.
+    ///
+    /// Heap buffer is reused as is, without copying or reallocation.
+    /// Inlined string is copied to a newly allocated buffer.
+    #[inline]
+    #[cfg(not(feature = "small-string"))]
+    pub fn into_string(self) -> String {
+        self.inner
+    }
+
+    /// Converts this string into a `String`.
+    ///
+    /// Heap buffer is reused as is, without copying or reallocation.
+    /// Inlined string has no heap buffer to reuse, so it is copied to a newly
+    /// allocated buffer of the exact size.
+    #[inline]
+    #[cfg(feature = "small-string")]
+    pub fn into_string(self) -> String {
+        match self.inner {
+            InlinableString::Heap(s) => s,
+            InlinableString::Inline(s) => String::from(AsRef::<str>::as_ref(&s)),
+        }
+    }
+
     /// Converts this string into a `Box<str>`.
+    ///
+    /// Heap buffer is adopted without copying if its capacity is the same as the length.
+    /// Otherwise, it is shrunk to fit, and the allocator may reallocate it.
     #[inline]
     #[cfg(not(feature = "small-string"))]
     pub fn into_boxed_str(self) -> Box<str> {
@@ -200,6 +619,10 @@ impl SmallString {
     }
 
     /// Converts this string into a `Box<str>`.
+    ///
+    /// Heap buffer is adopted without copying if its capacity is the same as the length.
+    /// Otherwise, it is shrunk to fit, and the allocator may reallocate it.
+    /// Inlined string is copied to a newly allocated buffer of the exact size.
     #[inline]
     #[cfg(feature = "small-string")]
     pub fn into_boxed_str(self) -> Box<str> {
@@ -211,9 +634,10 @@ impl SmallString {
 }
 
 impl From<String> for SmallString {
-    #[cfg_attr(not(feature = "small-string"), allow(clippy::identity_conversion))]
+    /// Converts the `String` following the default `InlinePolicy`.
+    #[inline]
     fn from(s: String) -> Self {
-        Self::from_inner(s.into())
+        Self::from_string_with(InlinePolicy::default(), s)
     }
 }
 
@@ -223,10 +647,32 @@ impl From<&'_ str> for SmallString {
     }
 }
 
-impl Into<String> for SmallString {
-    #[cfg_attr(not(feature = "small-string"), allow(clippy::identity_conversion))]
-    fn into(self) -> String {
-        self.inner.into()
+impl From<&'_ String> for SmallString {
+    #[inline]
+    fn from(s: &String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<Box<str>> for SmallString {
+    /// Adopts the heap buffer following the default `InlinePolicy`.
+    #[inline]
+    fn from(s: Box<str>) -> Self {
+        Self::from(s.into_string())
+    }
+}
+
+impl From<SmallString> for String {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_string()
+    }
+}
+
+impl From<SmallString> for Box<str> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_boxed_str()
     }
 }
 
@@ -236,78 +682,1058 @@ impl fmt::Display for SmallString {
     }
 }
 
-/// `String::retain()` for `InlinableString`.
-// Inefficient but fully safe.
-#[cfg(feature = "small-string")]
-fn retain<F>(this: &mut InlinableString, mut f: F)
-where
-    F: FnMut(char) -> bool,
-{
-    // Position of a character to check.
-    let mut idx = 0;
+impl fmt::Write for SmallString {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
 
-    while idx < this.len() {
-        // Get the character to check.
-        let ch = (&this[idx..])
-            .chars()
-            .next()
-            .expect("Should never fail: `this` has one or more valid character");
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
 
-        if f(ch) {
-            // Advance the cursor.
-            idx += ch.len_utf8();
-        } else {
-            // Remove the character.
-            this.remove(idx);
-        }
+impl Borrow<str> for SmallString {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
     }
 }
 
-/// `String::insert_str()` for `InlinableString`.
-// Inefficient but fully safe.
-#[cfg(feature = "small-string")]
-fn insert_str(this: &mut InlinableString, idx: usize, string: &str) {
-    match this {
-        InlinableString::Heap(ref mut s) => s.insert_str(idx, string),
-        InlinableString::Inline(ref mut s) => {
-            let new_len = s.len() + string.len();
-            if new_len > INLINE_STRING_CAPACITY {
-                let mut s = String::with_capacity(new_len);
-                s.push_str(&this[..idx]);
-                s.push_str(string);
-                s.push_str(&this[idx..]);
-                *this = InlinableString::Heap(s);
-                return;
-            }
-            let mut next_idx = idx;
-            for ch in string.chars() {
-                let ch_len = ch.len_utf8();
-                s.insert(next_idx, ch).expect(
-                    "Should never fail because `new_len` is \
-                     not greater than `INLINE_STRING_CAPACITY`",
-                );
-                next_idx += ch_len;
-            }
-        }
+impl BorrowMut<str> for SmallString {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut str {
+        self.as_mut_str()
     }
 }
 
-/// `String::split_off()` for `InlinableString`.
-// Unoptimized but fully `safe`.
-#[inline]
-#[cfg(feature = "small-string")]
-pub fn split_off(this: &mut InlinableString, at: usize) -> InlinableString {
-    let len = this.len();
+impl AsRef<str> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
 
-    assert!(at <= len, "`at` out of bounds");
+impl AsRef<[u8]> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
 
-    let other_len = len - at;
-    let mut other = InlinableString::with_capacity(other_len);
-    other.push_str(&this[at..]);
-    this.truncate(at);
+impl AsRef<OsStr> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.as_str().as_ref()
+    }
+}
 
-    assert_eq!(this.len(), at, "`this` has unexpected length");
-    assert_eq!(other.len(), other_len, "`other` has unexpected length");
+impl AsRef<Path> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.as_str().as_ref()
+    }
+}
 
-    other
+impl Deref for SmallString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl DerefMut for SmallString {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_str()
+    }
+}
+
+impl Add<&'_ str> for SmallString {
+    type Output = Self;
+
+    #[inline]
+    fn add(mut self, other: &str) -> Self::Output {
+        self.push_str(other);
+        self
+    }
+}
+
+impl AddAssign<&'_ str> for SmallString {
+    #[inline]
+    fn add_assign(&mut self, other: &str) {
+        self.push_str(other)
+    }
+}
+
+impl FromStr for SmallString {
+    type Err = Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(s))
+    }
+}
+
+impl From<SmallString> for Arc<str> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<SmallString> for Rc<str> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<SmallString> for Cow<'_, str> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        Cow::Owned(s.into_string())
+    }
+}
+
+impl From<SmallString> for Vec<u8> {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_bytes()
+    }
+}
+
+impl From<SmallString> for OsString {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_string().into()
+    }
+}
+
+impl From<SmallString> for PathBuf {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_string().into()
+    }
+}
+
+impl TryFrom<OsString> for SmallString {
+    /// The original string, which is not valid UTF-8.
+    type Error = OsString;
+
+    #[inline]
+    fn try_from(s: OsString) -> Result<Self, Self::Error> {
+        s.into_string().map(Into::into)
+    }
+}
+
+impl Extend<char> for SmallString {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|ch| self.push(ch));
+    }
+}
+
+impl<'a> Extend<&'a char> for SmallString {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = &'a char>,
+    {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<'a> Extend<&'a str> for SmallString {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        iter.into_iter().for_each(|s| self.push_str(s));
+    }
+}
+
+impl<'a> Extend<Cow<'a, str>> for SmallString {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = Cow<'a, str>>,
+    {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl Extend<String> for SmallString {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl Extend<SmallString> for SmallString {
+    #[inline]
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = SmallString>,
+    {
+        iter.into_iter().for_each(|s| self.push_str(&s));
+    }
+}
+
+impl FromIterator<char> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<&'a char> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = &'a char>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<&'a str> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> FromIterator<Cow<'a, str>> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = Cow<'a, str>>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<String> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<SmallString> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = SmallString>,
+    {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<SmallString> for String {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = SmallString>,
+    {
+        let mut s = String::new();
+        iter.into_iter().for_each(|piece| s.push_str(&piece));
+        s
+    }
+}
+
+impl FromIterator<SmallString> for Cow<'_, str> {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = SmallString>,
+    {
+        Cow::Owned(String::from_iter(iter))
+    }
+}
+
+macro_rules! impl_index_range {
+    ($($range:ty),*) => {
+        $(
+            impl Index<$range> for SmallString {
+                type Output = str;
+
+                #[inline]
+                fn index(&self, index: $range) -> &Self::Output {
+                    self.as_str().index(index)
+                }
+            }
+
+            impl IndexMut<$range> for SmallString {
+                #[inline]
+                fn index_mut(&mut self, index: $range) -> &mut Self::Output {
+                    self.as_mut_str().index_mut(index)
+                }
+            }
+        )*
+    };
+}
+
+impl_index_range!(
+    Range<usize>,
+    RangeFrom<usize>,
+    RangeFull,
+    RangeInclusive<usize>,
+    RangeTo<usize>,
+    RangeToInclusive<usize>
+);
+
+macro_rules! impl_cmp {
+    ($($other:ty),*) => {
+        $(
+            impl PartialEq<$other> for SmallString {
+                #[inline]
+                fn eq(&self, other: &$other) -> bool {
+                    PartialEq::eq(self.as_str(), &other[..])
+                }
+            }
+
+            impl PartialEq<SmallString> for $other {
+                #[inline]
+                fn eq(&self, other: &SmallString) -> bool {
+                    PartialEq::eq(&self[..], other.as_str())
+                }
+            }
+        )*
+    };
+}
+
+impl_cmp!(str, String, Cow<'_, str>);
+
+/// Number of bytes in a machine word.
+#[cfg(feature = "small-string")]
+const WORD_BYTES: usize = mem::size_of::<usize>();
+
+/// Returns the bytes of the string if it is inlined.
+#[inline]
+#[cfg(feature = "small-string")]
+fn inline_bytes(s: &InlinableString) -> Option<&[u8]> {
+    match s {
+        InlinableString::Inline(s) => Some(s.as_bytes()),
+        InlinableString::Heap(_) => None,
+    }
+}
+
+/// Returns the bytes in the chunk as an array of a word size.
+#[inline]
+#[cfg(feature = "small-string")]
+fn word_bytes(chunk: &[u8]) -> [u8; WORD_BYTES] {
+    <[u8; WORD_BYTES]>::try_from(chunk).expect("Should never fail: the chunk is a word")
+}
+
+/// Compares the byte slices for equality, word by word.
+#[inline]
+#[cfg(feature = "small-string")]
+fn words_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    if lhs.len() != rhs.len() {
+        return false;
+    }
+    let lhs_chunks = lhs.chunks_exact(WORD_BYTES);
+    let rhs_chunks = rhs.chunks_exact(WORD_BYTES);
+    let (lhs_rest, rhs_rest) = (lhs_chunks.remainder(), rhs_chunks.remainder());
+    lhs_chunks.zip(rhs_chunks).all(|(lhs, rhs)| {
+        usize::from_ne_bytes(word_bytes(lhs)) == usize::from_ne_bytes(word_bytes(rhs))
+    }) && lhs_rest == rhs_rest
+}
+
+/// Compares the byte slices lexicographically, word by word.
+///
+/// Words are loaded as big-endian integers, so that the first differing byte decides the order.
+#[inline]
+#[cfg(feature = "small-string")]
+fn words_cmp(lhs: &[u8], rhs: &[u8]) -> Ordering {
+    let common_len = lhs.len().min(rhs.len());
+    let lhs_chunks = lhs[..common_len].chunks_exact(WORD_BYTES);
+    let rhs_chunks = rhs[..common_len].chunks_exact(WORD_BYTES);
+    let (lhs_rest, rhs_rest) = (lhs_chunks.remainder(), rhs_chunks.remainder());
+    lhs_chunks
+        .zip(rhs_chunks)
+        .map(|(lhs, rhs)| {
+            usize::from_be_bytes(word_bytes(lhs)).cmp(&usize::from_be_bytes(word_bytes(rhs)))
+        })
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or_else(|| lhs_rest.cmp(rhs_rest))
+        .then_with(|| lhs.len().cmp(&rhs.len()))
+}
+
+impl PartialEq for SmallString {
+    /// Inline strings are compared word by word.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "small-string")]
+        {
+            if let (Some(lhs), Some(rhs)) = (inline_bytes(&self.inner), inline_bytes(&other.inner))
+            {
+                return words_eq(lhs, rhs);
+            }
+        }
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialOrd for SmallString {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    /// Inline strings are compared word by word, as big-endian integers.
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        #[cfg(feature = "small-string")]
+        {
+            if let (Some(lhs), Some(rhs)) = (inline_bytes(&self.inner), inline_bytes(&other.inner))
+            {
+                return words_cmp(lhs, rhs);
+            }
+        }
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallString {
+    /// Hashes the string as `str` does.
+    ///
+    /// There is no fast path for inline strings: the hash must be the same as `str` for lookups
+    /// through `Borrow<str>`, and `str` feeds all the bytes and a terminator to the hasher.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+/// Draining iterator for the string.
+#[derive(Debug)]
+#[cfg(not(feature = "small-string"))]
+pub struct Drain<'a> {
+    /// Inner iterator.
+    inner: std::string::Drain<'a>,
+}
+
+#[cfg(not(feature = "small-string"))]
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(not(feature = "small-string"))]
+impl DoubleEndedIterator for Drain<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.inner.next_back()
+    }
+}
+
+/// Draining iterator for the string.
+#[derive(Debug)]
+#[cfg(feature = "small-string")]
+pub struct Drain<'a> {
+    /// Removed string.
+    removed: InlinableString,
+    /// Start position of characters not yet iterated.
+    front: usize,
+    /// End position of characters not yet iterated.
+    back: usize,
+    /// Marker for compatibility with `std::string::Drain`.
+    _string: PhantomData<&'a mut SmallString>,
+}
+
+#[cfg(feature = "small-string")]
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        let ch = self.removed[self.front..self.back].chars().next()?;
+        self.front += ch.len_utf8();
+        Some(ch)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.removed[self.front..self.back].chars().size_hint()
+    }
+}
+
+#[cfg(feature = "small-string")]
+impl DoubleEndedIterator for Drain<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.removed[self.front..self.back].chars().next_back()?;
+        self.back -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl FusedIterator for Drain<'_> {}
+
+/// Converts the range bounds into a range, checking it is in `0..=len`.
+#[cfg(feature = "small-string")]
+fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range start is greater than range end");
+    assert!(end <= len, "range end out of bounds");
+
+    start..end
+}
+
+/// `String::replace_range()` for `InlinableString`.
+// Inefficient but fully safe.
+#[cfg(feature = "small-string")]
+fn replace_range(this: &mut InlinableString, range: Range<usize>, replace_with: &str) {
+    match this {
+        InlinableString::Heap(ref mut s) => s.replace_range(range, replace_with),
+        InlinableString::Inline(_) => {
+            // Slicing panics if the range is not on char boundaries.
+            let new_len = this.len() - this[range.clone()].len() + replace_with.len();
+            let mut s = InlinableString::with_capacity(new_len);
+            s.push_str(&this[..range.start]);
+            s.push_str(replace_with);
+            s.push_str(&this[range.end..]);
+            *this = s;
+        }
+    }
+}
+
+/// `String::retain()` for `InlinableString`.
+// Inefficient but fully safe.
+#[cfg(feature = "small-string")]
+fn retain<F>(this: &mut InlinableString, mut f: F)
+where
+    F: FnMut(char) -> bool,
+{
+    // Position of a character to check.
+    let mut idx = 0;
+
+    while idx < this.len() {
+        // Get the character to check.
+        let ch = (&this[idx..])
+            .chars()
+            .next()
+            .expect("Should never fail: `this` has one or more valid character");
+
+        if f(ch) {
+            // Advance the cursor.
+            idx += ch.len_utf8();
+        } else {
+            // Remove the character.
+            this.remove(idx);
+        }
+    }
+}
+
+/// `String::insert_str()` for `InlinableString`.
+// Inefficient but fully safe.
+#[cfg(feature = "small-string")]
+fn insert_str(this: &mut InlinableString, idx: usize, string: &str) {
+    match this {
+        InlinableString::Heap(ref mut s) => s.insert_str(idx, string),
+        InlinableString::Inline(ref mut s) => {
+            let new_len = s.len() + string.len();
+            if new_len > INLINE_STRING_CAPACITY {
+                let mut s = String::with_capacity(new_len);
+                s.push_str(&this[..idx]);
+                s.push_str(string);
+                s.push_str(&this[idx..]);
+                *this = InlinableString::Heap(s);
+                return;
+            }
+            let mut next_idx = idx;
+            for ch in string.chars() {
+                let ch_len = ch.len_utf8();
+                s.insert(next_idx, ch).expect(
+                    "Should never fail because `new_len` is \
+                     not greater than `INLINE_STRING_CAPACITY`",
+                );
+                next_idx += ch_len;
+            }
+        }
+    }
+}
+
+/// `String::split_off()` for `InlinableString`.
+// Unoptimized but fully `safe`.
+#[inline]
+#[cfg(feature = "small-string")]
+pub fn split_off(this: &mut InlinableString, at: usize) -> InlinableString {
+    let len = this.len();
+
+    assert!(at <= len, "`at` out of bounds");
+
+    let other_len = len - at;
+    let mut other = InlinableString::with_capacity(other_len);
+    other.push_str(&this[at..]);
+    this.truncate(at);
+
+    assert_eq!(this.len(), at, "`this` has unexpected length");
+    assert_eq!(other.len(), other_len, "`other` has unexpected length");
+
+    other
+}
+
+/// Returns the string with the given matches replaced.
+///
+/// The pattern is searched only once for each match, and the result is built
+/// from an empty string, so that it stays inline if it fits.
+fn replaced<I>(s: &str, matches: I, to: &str) -> SmallString
+where
+    I: Iterator<Item = (usize, usize)>,
+{
+    let mut replaced = SmallString::new();
+    // `s[..pushed]` is already pushed to `replaced`.
+    let mut pushed = 0;
+    for (start, end) in matches {
+        replaced.push_str(&s[pushed..start]);
+        replaced.push_str(to);
+        pushed = end;
+    }
+    replaced.push_str(&s[pushed..]);
+
+    replaced
+}
+
+/// Conversion into a `SmallString`.
+///
+/// This is intended to be used as a bound of setter arguments, such as
+/// `fn set_id(&mut self, id: impl IntoSmallString)`.
+///
+/// Owned heap strings are adopted without copying if they are too long to be inlined,
+/// and short strings are copied into the inline buffer if the backend supports it.
+pub trait IntoSmallString {
+    /// Converts the value into a `SmallString`.
+    fn into_small_string(self) -> SmallString;
+}
+
+impl IntoSmallString for SmallString {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self
+    }
+}
+
+impl IntoSmallString for &'_ SmallString {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.clone()
+    }
+}
+
+impl IntoSmallString for &'_ str {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for String {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for &'_ String {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.as_str().into()
+    }
+}
+
+impl IntoSmallString for Box<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for Cow<'_, str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        match self {
+            Cow::Borrowed(s) => s.into(),
+            Cow::Owned(s) => s.into(),
+        }
+    }
+}
+
+impl IntoSmallString for SmallCow<'_> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into_owned()
+    }
+}
+
+impl IntoSmallString for Arc<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        (*self).into()
+    }
+}
+
+impl IntoSmallString for Rc<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        (*self).into()
+    }
+}
+
+impl IntoSmallString for char {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        let mut s = SmallString::with_capacity(self.len_utf8());
+        s.push(self);
+        s
+    }
+}
+
+macro_rules! impl_into_small_string_for_display {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSmallString for $ty {
+                #[inline]
+                fn into_small_string(self) -> SmallString {
+                    use std::fmt::Write;
+
+                    let mut s = SmallString::new();
+                    write!(s, "{}", self).expect("Should never fail: `SmallString` never fails to write");
+                    s
+                }
+            }
+        )*
+    };
+}
+
+impl_into_small_string_for_display!(f32, f64);
+
+macro_rules! impl_into_small_string_for_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSmallString for $ty {
+                #[inline]
+                fn into_small_string(self) -> SmallString {
+                    SmallString::from_dec(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_small_string_for_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    thread_local! {
+        /// Number of allocations and reallocations on the current thread.
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Allocator which counts allocations and reallocations.
+    struct CountingAlloc;
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Calls the function, and returns the result and the number of allocations.
+    fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+        (result, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    /// Returns a heap string longer than `INLINE_CAPACITY`.
+    fn heap_string(capacity: usize) -> SmallString {
+        let mut s = String::with_capacity(capacity);
+        s.push_str("a string too long to be inlined");
+        assert!(s.len() > INLINE_CAPACITY);
+        SmallString::from_string_with(InlinePolicy::AlwaysAdopt, s)
+    }
+
+    #[test]
+    fn take_never_allocates() {
+        let mut s = heap_string(64);
+        let ptr = s.as_ptr();
+        let (taken, allocs) = count_allocations(|| s.take());
+        assert_eq!(allocs, 0);
+        assert_eq!(taken.as_ptr(), ptr);
+        assert!(s.is_empty());
+
+        let mut s = SmallString::from("short");
+        let (taken, allocs) = count_allocations(|| s.take());
+        assert_eq!(allocs, 0);
+        assert_eq!(taken.as_str(), "short");
+    }
+
+    #[test]
+    fn into_string_adopts_heap_buffer() {
+        let s = heap_string(64);
+        let ptr = s.as_ptr();
+        let (string, allocs) = count_allocations(|| s.into_string());
+        assert_eq!(allocs, 0);
+        assert_eq!(string.as_ptr(), ptr);
+        assert_eq!(string.capacity(), 64);
+    }
+
+    #[test]
+    fn into_boxed_str_adopts_exact_capacity_buffer() {
+        let s = heap_string(0);
+        assert_eq!(s.capacity(), s.len());
+        let ptr = s.as_ptr();
+        let (boxed, allocs) = count_allocations(|| Box::<str>::from(s));
+        assert_eq!(allocs, 0);
+        assert_eq!(boxed.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn into_small_string_adopts_long_string() {
+        let s = String::from("a string too long to be inlined");
+        let ptr = s.as_ptr();
+        let (small, allocs) = count_allocations(|| s.into_small_string());
+        assert_eq!(allocs, 0);
+        assert_eq!(small.as_ptr(), ptr);
+
+        let boxed: Box<str> = "a string too long to be inlined".into();
+        let ptr = boxed.as_ptr();
+        let (small, allocs) = count_allocations(|| boxed.into_small_string());
+        assert_eq!(allocs, 0);
+        assert_eq!(small.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn replacen_in_place_searches_once() {
+        let mut calls = 0;
+        let mut s = SmallString::from("a-b-c");
+        s.replacen_in_place(
+            |ch: char| {
+                calls += 1;
+                ch == '-'
+            },
+            "+",
+            1,
+        );
+        assert_eq!(s.as_str(), "a+b-c");
+        assert_eq!(calls, 2);
+
+        let mut s = SmallString::from("abc");
+        s.replace_in_place("", "-");
+        assert_eq!(s.as_str(), "-a-b-c-");
+    }
+
+    #[test]
+    #[cfg(feature = "small-string")]
+    fn replace_in_place_inlines_result() {
+        let mut s = SmallString::from("id-with-many-hyphens-to-be-removed");
+        assert!(s.len() > INLINE_CAPACITY);
+        let ((), allocs) = count_allocations(|| s.replace_in_place('-', ""));
+        assert_eq!(allocs, 0);
+        assert_eq!(s.as_str(), "idwithmanyhyphenstoberemoved");
+        assert!(matches!(s.inner, InlinableString::Inline(_)));
+    }
+
+    #[test]
+    fn from_utf8_lossy_matches_string() {
+        for &bytes in &[
+            &b"valid"[..],
+            b"",
+            b"ab\xFFcd",
+            b"\xFF\xFE",
+            b"a\xE3\x81",
+            b"\xE3\x81\xE3\x81\x82",
+            b"\xF0\x90\x80x",
+        ] {
+            let lossy = SmallString::from_utf8_lossy(bytes);
+            assert_eq!(&*lossy, &*String::from_utf8_lossy(bytes), "{:?}", bytes);
+            assert_eq!(
+                matches!(lossy, SmallCow::Borrowed(_)),
+                std::str::from_utf8(bytes).is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn read_line_from_appends_line() {
+        let mut reader = std::io::Cursor::new(&b"first\r\nsecond"[..]);
+        let mut line = SmallString::from("> ");
+        assert_eq!(
+            line.read_line_from(&mut reader, LineEnding::Strip).unwrap(),
+            7
+        );
+        assert_eq!(line.as_str(), "> first");
+        line.clear();
+        assert_eq!(
+            line.read_line_from(&mut reader, LineEnding::Keep).unwrap(),
+            6
+        );
+        assert_eq!(line.as_str(), "second");
+        assert_eq!(
+            line.read_line_from(&mut reader, LineEnding::Keep).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn with_c_str_does_not_allocate_for_short_string() {
+        let s = SmallString::from("file.png");
+        let (len, allocs) = count_allocations(|| s.with_c_str(|c| c.to_bytes_with_nul().len()));
+        assert_eq!(allocs, 0);
+        assert_eq!(len.unwrap(), 9);
+        assert!(SmallString::from("a\0b").with_c_str(|_| ()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "small-string")]
+    fn inline_string_is_copied_once() {
+        let s = SmallString::from("short");
+        let (string, allocs) = count_allocations(|| s.clone().into_string());
+        assert_eq!(allocs, 1);
+        assert_eq!(string.capacity(), string.len());
+
+        let (boxed, allocs) = count_allocations(|| Box::<str>::from(s));
+        assert_eq!(allocs, 1);
+        assert_eq!(&*boxed, "short");
+    }
+
+    #[test]
+    fn cmp_matches_str() {
+        let strs = [
+            "",
+            "\0",
+            "a",
+            "a\0",
+            "ab",
+            "abcdefgh",
+            "abcdefgh\0",
+            "abcdefgi",
+            "abcdefghijklmnop",
+            "abcdefghijklmnopq",
+            "\u{3042}",
+            "\u{3042}\u{3044}",
+            "a very long string to be put on the heap",
+        ];
+        for &lhs in &strs {
+            for &rhs in &strs {
+                let (small_lhs, small_rhs) = (SmallString::from(lhs), SmallString::from(rhs));
+                assert_eq!(small_lhs == small_rhs, lhs == rhs, "{:?} == {:?}", lhs, rhs);
+                assert_eq!(
+                    small_lhs.cmp(&small_rhs),
+                    lhs.cmp(rhs),
+                    "{:?} cmp {:?}",
+                    lhs,
+                    rhs
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hash_matches_str() {
+        use std::collections::{hash_map::DefaultHasher, HashMap};
+
+        let hash = |v: &dyn Fn(&mut DefaultHasher)| {
+            let mut hasher = DefaultHasher::new();
+            v(&mut hasher);
+            hasher.finish()
+        };
+        for &key in &["", "key", "a very long string to be put on the heap"] {
+            let small = SmallString::from(key);
+            assert_eq!(hash(&|h| small.hash(h)), hash(&|h| key.hash(h)));
+        }
+
+        let mut map = HashMap::new();
+        map.insert(SmallString::from("key"), 1);
+        map.insert(
+            SmallString::from("a very long string to be put on the heap"),
+            2,
+        );
+        assert_eq!(map.get("key"), Some(&1));
+        assert_eq!(
+            map.get("a very long string to be put on the heap"),
+            Some(&2)
+        );
+        assert_eq!(map.get("missing"), None);
+    }
 }
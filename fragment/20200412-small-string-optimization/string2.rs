@@ -2,20 +2,78 @@
 
 use std::{
     borrow::Cow,
-    convert::Infallible,
+    convert::{Infallible, TryFrom},
+    ffi::{CStr, CString, NulError, OsStr, OsString},
     fmt,
     iter::FromIterator,
     ops::{
         Index, IndexMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
     },
+    path::{Path, PathBuf},
     rc::Rc,
-    string::FromUtf8Error,
+    str::Utf8Error,
     sync::Arc,
 };
 
+use crate::{
+    cow::SmallCow,
+    error::SmallFromUtf8Error,
+    format::{Digits, Integer},
+    io::LineEnding,
+    pattern::{Matches, Pattern, Split},
+    slug::SlugOptions,
+};
+
 /// Backend string type.
 type BackendString = String;
 
+/// Maximum length of strings regarded as short enough to be inlined.
+///
+/// The backend has no inline buffer, so this is the length which a typical
+/// small string type can store in the space of `String`.
+pub(crate) const INLINE_CAPACITY: usize = std::mem::size_of::<String>() - 1;
+
+/// Size of the stack buffer used to create a C string without allocation.
+const C_STR_STACK_BUFFER_LEN: usize = 128;
+
+/// Policy on whether to adopt the buffer of a `String` when converting it into a `SmallString`.
+///
+/// "Inlining" means copying the content into the inline buffer.
+/// The backend string type has no inline buffer, so inlining here copies the
+/// content into a newly allocated buffer of the exact size and frees the original buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlinePolicy {
+    /// Always adopts the heap buffer.
+    AlwaysAdopt,
+    /// Inlines the string if it is short enough.
+    InlineWhenFits,
+    /// Inlines the string if it is short enough and the buffer has much excess capacity.
+    InlineWhenWasteful,
+}
+
+impl InlinePolicy {
+    /// Returns `true` if a string with the given length and capacity should be inlined.
+    #[inline]
+    fn should_inline(self, len: usize, capacity: usize) -> bool {
+        match self {
+            InlinePolicy::AlwaysAdopt => false,
+            InlinePolicy::InlineWhenFits => len <= INLINE_CAPACITY,
+            InlinePolicy::InlineWhenWasteful => len <= INLINE_CAPACITY && capacity / 2 > len,
+        }
+    }
+}
+
+impl Default for InlinePolicy {
+    /// Returns the policy used by `From<String> for SmallString`.
+    ///
+    /// Since inlining requires an allocation with this backend,
+    /// only wasteful buffers are replaced.
+    #[inline]
+    fn default() -> Self {
+        InlinePolicy::InlineWhenWasteful
+    }
+}
+
 /// Owned string type with optional small string optimization.
 ///
 /// In some use cases, strings are usually short.
@@ -31,8 +89,6 @@ pub struct SmallString {
 
 // Some methods in `std::string::String` are not implemented, here is why:
 //
-//  * `from_utf8_lossy
-//      + `Cow<str>` is tied to `String`.
 //  * `from_utf16`, `from_utf16_lossy`
 //      + Low priority.
 //  * `from_raw_parts`
@@ -43,8 +99,6 @@ pub struct SmallString {
 //        string type can be fully controlled.
 //  * `as_mut_vec`
 //      + `SmallString` does not guarantee that the internal representation is `Vec<u8>`.
-//  * `drain`
-//      + Is complex and maybe hard to implement correctly.
 impl SmallString {
     /// Creates a new empty `SmallString`.
     // This is not `const` because I am unsure it can be guaranteed for other backends.
@@ -65,10 +119,111 @@ impl SmallString {
         Self::from_inner(BackendString::with_capacity(capacity))
     }
 
+    /// Creates a new `SmallString` from the given `String`, following the given policy.
+    #[inline]
+    pub fn from_string_with(policy: InlinePolicy, s: String) -> Self {
+        if policy.should_inline(s.len(), s.capacity()) {
+            Self::from(s.as_str())
+        } else {
+            Self::from_inner(s)
+        }
+    }
+
+    /// Concatenates the given strings, placing the separator between each.
+    ///
+    /// The length of the result is computed first, so this allocates at most once.
+    pub fn join<S: AsRef<str>>(pieces: &[S], sep: &str) -> Self {
+        let len = pieces
+            .iter()
+            .map(|s| s.as_ref().len())
+            .try_fold(0_usize, usize::checked_add)
+            .and_then(|len| {
+                sep.len()
+                    .checked_mul(pieces.len().saturating_sub(1))
+                    .and_then(|sep_len| len.checked_add(sep_len))
+            })
+            .expect("Attempt to join into a string with overflowing length");
+
+        let mut joined = Self::with_capacity(len);
+        let mut pieces = pieces.iter();
+        if let Some(first) = pieces.next() {
+            joined.push_str(first.as_ref());
+            for piece in pieces {
+                joined.push_str(sep);
+                joined.push_str(piece.as_ref());
+            }
+        }
+        debug_assert_eq!(joined.len(), len);
+
+        joined
+    }
+
+    /// Concatenates the given strings.
+    ///
+    /// The length of the result is computed first, so this allocates at most once.
+    #[inline]
+    pub fn concat<S: AsRef<str>>(pieces: &[S]) -> Self {
+        Self::join(pieces, "")
+    }
+
+    /// Creates a new `SmallString` from the decimal representation of the integer.
+    ///
+    /// This is faster than formatting by `std::fmt`.
+    #[inline]
+    pub fn from_dec<I: Integer>(n: I) -> Self {
+        let digits = n.to_dec_digits();
+        Self::from(digits.as_str())
+    }
+
+    /// Creates a new `SmallString` from the lower-case hexadecimal representation of the integer.
+    ///
+    /// This is faster than formatting by `std::fmt`.
+    #[inline]
+    pub fn from_hex<I: Integer>(n: I) -> Self {
+        let digits = n.to_hex_digits();
+        Self::from(digits.as_str())
+    }
+
     /// Converts a vector of bytes to a `SmallString`.
+    ///
+    /// On error, the original vector can be taken back from the error without copying.
+    #[inline]
+    pub fn from_utf8(vec: Vec<u8>) -> Result<Self, SmallFromUtf8Error> {
+        BackendString::from_utf8(vec)
+            .map(Self::from_inner)
+            .map_err(Into::into)
+    }
+
+    /// Converts a slice of bytes to a `SmallString`.
+    ///
+    /// Unlike `from_utf8()`, this does not require a `Vec<u8>`, and the content is
+    /// copied into the inline buffer if it fits.
+    ///
+    /// On error, the bytes are not copied, since the caller still has them.
     #[inline]
-    pub fn from_utf8(vec: Vec<u8>) -> Result<Self, FromUtf8Error> {
-        BackendString::from_utf8(vec).map(Self::from_inner)
+    pub fn from_utf8_slice(v: &[u8]) -> Result<Self, Utf8Error> {
+        std::str::from_utf8(v).map(Self::from)
+    }
+
+    /// Converts a slice of bytes to a `SmallString` without checking that the
+    /// string contains valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// `v` argument should be valid UTF-8 sequence.
+    /// See the documentation for `std::str::from_utf8_unchecked()`.
+    #[inline]
+    pub unsafe fn from_utf8_unchecked_slice(v: &[u8]) -> Self {
+        Self::from(std::str::from_utf8_unchecked(v))
+    }
+
+    /// Converts a slice of bytes to a string, including invalid characters.
+    ///
+    /// Invalid UTF-8 sequences are replaced with U+FFFD REPLACEMENT CHARACTER.
+    /// Returns the borrowed string if `v` is valid UTF-8.
+    #[inline]
+    pub fn from_utf8_lossy(v: &[u8]) -> SmallCow<'_> {
+        BackendString::from_utf8_lossy(v).into()
     }
 
     /// Converts a vector of bytes to a `SmallString` without checking that the
@@ -107,6 +262,45 @@ impl SmallString {
         self.inner.push_str(string)
     }
 
+    /// Appends the decimal representation of the integer.
+    #[inline]
+    pub fn push_dec<I: Integer>(&mut self, n: I) {
+        self.push_str(n.to_dec_digits().as_str())
+    }
+
+    /// Appends the decimal representation of the integer, padded with zeros to `width`.
+    ///
+    /// This is same as formatting with `{:0width$}`: the sign is counted as a part of the width.
+    #[inline]
+    pub fn push_dec_padded<I: Integer>(&mut self, n: I, width: usize) {
+        self.push_digits_padded(n.to_dec_digits(), width)
+    }
+
+    /// Appends the lower-case hexadecimal representation of the integer.
+    #[inline]
+    pub fn push_hex<I: Integer>(&mut self, n: I) {
+        self.push_str(n.to_hex_digits().as_str())
+    }
+
+    /// Appends the lower-case hexadecimal representation of the integer,
+    /// padded with zeros to `width`.
+    #[inline]
+    pub fn push_hex_padded<I: Integer>(&mut self, n: I, width: usize) {
+        self.push_digits_padded(n.to_hex_digits(), width)
+    }
+
+    /// Appends the formatted integer, padded with zeros to `width`.
+    fn push_digits_padded(&mut self, digits: Digits, width: usize) {
+        let (sign, digits) = digits.sign_and_digits();
+        let padding = width.saturating_sub(sign.len() + digits.len());
+        self.reserve(sign.len() + padding + digits.len());
+        self.push_str(sign);
+        for _ in 0..padding {
+            self.push('0');
+        }
+        self.push_str(digits);
+    }
+
     /// Returns this `SmallString`'s capacity, in bytes.
     #[inline]
     pub fn capacity(&self) -> usize {
@@ -216,7 +410,176 @@ impl SmallString {
         self.inner.replace_range(range, replace_with)
     }
 
+    /// Removes the specified range from the string, and returns the removed characters.
+    #[inline]
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_>
+    where
+        R: std::ops::RangeBounds<usize>,
+    {
+        Drain {
+            inner: self.inner.drain(range),
+        }
+    }
+
+    /// Replaces all matches of a pattern with another string, and returns the result.
+    ///
+    /// Unlike `str::replace()`, this returns a `SmallString`.
+    #[inline]
+    pub fn replace<P: Pattern>(&self, from: P, to: &str) -> Self {
+        self.replacen(from, to, usize::MAX)
+    }
+
+    /// Replaces first `count` matches of a pattern with another string, and returns the result.
+    ///
+    /// Unlike `str::replacen()`, this returns a `SmallString`.
+    #[inline]
+    pub fn replacen<P: Pattern>(&self, from: P, to: &str, count: usize) -> Self {
+        replaced(self, Matches::new(self, from).take(count), to)
+    }
+
+    /// Replaces all matches of a pattern with another string.
+    ///
+    /// If the pattern does not match, the string is not modified and no allocation happens.
+    #[inline]
+    pub fn replace_in_place<P: Pattern>(&mut self, from: P, to: &str) {
+        self.replacen_in_place(from, to, usize::MAX)
+    }
+
+    /// Replaces first `count` matches of a pattern with another string.
+    ///
+    /// If the pattern does not match, the string is not modified and no allocation happens.
+    pub fn replacen_in_place<P: Pattern>(&mut self, from: P, to: &str, count: usize) {
+        let mut matches = Matches::new(self, from).take(count).peekable();
+        if matches.peek().is_none() {
+            return;
+        }
+
+        *self = replaced(self, matches, to);
+    }
+
+    /// Calls the given function with the NUL-terminated copy of the string.
+    ///
+    /// If the string is shorter than 128 bytes, it is NUL-terminated in a
+    /// buffer on the stack, so no allocation happens.
+    /// Otherwise, a `CString` is allocated.
+    ///
+    /// The backend has no inline buffer, and the spare capacity of the heap
+    /// buffer cannot be written through `&self`.
+    /// Copying a short string to the stack is as cheap as NUL-terminating it in
+    /// an inline buffer would be, and never allocates.
+    ///
+    /// Returns an error if the string contains a NUL byte.
+    pub fn with_c_str<F, R>(&self, f: F) -> Result<R, NulError>
+    where
+        F: FnOnce(&CStr) -> R,
+    {
+        let bytes = self.as_bytes();
+        if bytes.len() >= C_STR_STACK_BUFFER_LEN {
+            return CString::new(bytes).map(|s| f(&s));
+        }
+
+        let mut buf = [0_u8; C_STR_STACK_BUFFER_LEN];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        match CStr::from_bytes_with_nul(&buf[..=bytes.len()]) {
+            Ok(s) => Ok(f(s)),
+            // The error is created by `CString::new()`, since `NulError` has no public constructor.
+            // This allocates, but only on error.
+            Err(_) => Err(CString::new(bytes)
+                .expect_err("Should never fail: the string has an interior NUL byte")),
+        }
+    }
+
+    /// Returns an iterator of substrings separated by the pattern, as `SmallString`s.
+    ///
+    /// This is `str::split()` returning `SmallString`s.
+    #[inline]
+    pub fn split_small<'a, P>(&'a self, pattern: P) -> impl Iterator<Item = SmallString> + 'a
+    where
+        P: Pattern + 'a,
+    {
+        Split::new(self, pattern).map(SmallString::from)
+    }
+
+    /// Splits the string on the first match of the pattern.
+    #[inline]
+    pub fn split_once_small<P: Pattern>(&self, mut pattern: P) -> Option<(Self, Self)> {
+        pattern
+            .find_in(self)
+            .map(|(start, end)| (self[..start].into(), self[end..].into()))
+    }
+
+    /// Splits the string on the last match of the pattern.
+    #[inline]
+    pub fn rsplit_once_small<P: Pattern>(&self, mut pattern: P) -> Option<(Self, Self)> {
+        pattern
+            .rfind_in(self)
+            .map(|(start, end)| (self[..start].into(), self[end..].into()))
+    }
+
+    /// Returns an iterator of substrings separated by whitespace, as `SmallString`s.
+    ///
+    /// This is `str::split_whitespace()` returning `SmallString`s.
+    #[inline]
+    pub fn split_whitespace_small(&self) -> impl Iterator<Item = SmallString> + '_ {
+        self.split_whitespace().map(SmallString::from)
+    }
+
+    /// Reads a line from the reader, and appends it to this `SmallString`.
+    ///
+    /// The bytes are validated as UTF-8 directly from the buffer of the reader.
+    /// Clear the string before calling this in order to reuse the buffer for multiple lines.
+    ///
+    /// Returns the number of bytes consumed, including the line ending.
+    /// `Ok(0)` means the reader has reached EOF.
+    /// On error, the string is restored to its original content.
+    #[inline]
+    pub fn read_line_from<R: std::io::BufRead + ?Sized>(
+        &mut self,
+        reader: &mut R,
+        line_ending: LineEnding,
+    ) -> std::io::Result<usize> {
+        crate::io::read_line(self, reader, line_ending)
+    }
+
+    /// Creates a slug of the given string, e.g. for anchor ids from section titles.
+    ///
+    /// Letters and digits are kept, and runs of the other characters become a separator.
+    #[inline]
+    pub fn slugify(s: &str, options: SlugOptions) -> Self {
+        let mut slug = Self::new();
+        slug.push_slug(s, options);
+        slug
+    }
+
+    /// Appends the slug of the given string.
+    ///
+    /// `max_len` of the options limits the length of the appended part.
+    #[inline]
+    pub fn push_slug(&mut self, s: &str, options: SlugOptions) {
+        crate::slug::push_slug(self, s, &options)
+    }
+
+    /// Takes the string out, leaving an empty `SmallString` in its place.
+    ///
+    /// This never allocates nor copies the content.
+    #[inline]
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    /// Converts this `SmallString` into a `String`.
+    ///
+    /// If the string is on the heap, the buffer is reused as is, without copying or reallocation.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.inner
+    }
+
     /// Converts this `SmallString` into a `Box<str>`.
+    ///
+    /// If the string is on the heap and its capacity is the same as the length,
+    /// the buffer is adopted without copying.
+    /// Otherwise, the buffer is shrunk to fit, and the allocator may reallocate it.
     #[inline]
     pub fn into_boxed_str(self) -> Box<str> {
         self.inner.into_boxed_str()
@@ -253,6 +616,20 @@ impl AsRef<str> for SmallString {
     }
 }
 
+impl AsRef<OsStr> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.inner.as_ref()
+    }
+}
+
+impl AsRef<Path> for SmallString {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.inner.as_ref()
+    }
+}
+
 impl std::borrow::Borrow<str> for SmallString {
     #[inline]
     fn borrow(&self) -> &str {
@@ -290,6 +667,20 @@ impl std::fmt::Display for SmallString {
     }
 }
 
+impl fmt::Write for SmallString {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
 impl<'a> std::iter::Extend<&'a char> for SmallString {
     #[inline]
     fn extend<I>(&mut self, iter: I)
@@ -351,16 +742,19 @@ impl std::iter::Extend<char> for SmallString {
 }
 
 impl From<String> for SmallString {
+    /// Converts the `String` following the default [`InlinePolicy`].
+    ///
+    /// [`InlinePolicy`]: enum.InlinePolicy.html
     #[inline]
     fn from(s: String) -> Self {
-        Self::from_inner(s)
+        Self::from_string_with(InlinePolicy::default(), s)
     }
 }
 
 impl From<SmallString> for String {
     #[inline]
     fn from(s: SmallString) -> Self {
-        s.inner
+        s.into_string()
     }
 }
 
@@ -388,7 +782,7 @@ impl From<Box<str>> for SmallString {
 impl From<SmallString> for Box<str> {
     #[inline]
     fn from(s: SmallString) -> Self {
-        s.inner.into()
+        s.into_boxed_str()
     }
 }
 
@@ -413,6 +807,30 @@ impl From<SmallString> for Cow<'_, str> {
     }
 }
 
+impl From<SmallString> for OsString {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_string().into()
+    }
+}
+
+impl From<SmallString> for PathBuf {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        s.into_string().into()
+    }
+}
+
+impl TryFrom<OsString> for SmallString {
+    /// The original string, which is not valid UTF-8.
+    type Error = OsString;
+
+    #[inline]
+    fn try_from(s: OsString) -> Result<Self, Self::Error> {
+        s.into_string().map(Into::into)
+    }
+}
+
 impl From<SmallString> for Vec<u8> {
     #[inline]
     fn from(s: SmallString) -> Self {
@@ -430,6 +848,16 @@ impl<'a> FromIterator<&'a char> for SmallString {
     }
 }
 
+impl<'a> FromIterator<&'a str> for SmallString {
+    #[inline]
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        Self::from_inner(BackendString::from_iter(iter))
+    }
+}
+
 impl<'a> FromIterator<Cow<'a, str>> for SmallString {
     #[inline]
     fn from_iter<I>(iter: I) -> Self
@@ -547,3 +975,316 @@ macro_rules! impl_cmp {
 impl_cmp!(str);
 impl_cmp!(String);
 impl_cmp!(Cow<'_, str>);
+
+/// Draining iterator for `SmallString`.
+#[derive(Debug)]
+pub struct Drain<'a> {
+    /// Inner iterator.
+    inner: std::string::Drain<'a>,
+}
+
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Drain<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        self.inner.next_back()
+    }
+}
+
+impl std::iter::FusedIterator for Drain<'_> {}
+
+/// Returns the string with the given matches replaced.
+///
+/// The pattern is searched only once for each match, and the result is built
+/// from an empty string, so that it is not allocated larger than needed.
+fn replaced<I>(s: &str, matches: I, to: &str) -> SmallString
+where
+    I: Iterator<Item = (usize, usize)>,
+{
+    let mut replaced = SmallString::new();
+    // `s[..pushed]` is already pushed to `replaced`.
+    let mut pushed = 0;
+    for (start, end) in matches {
+        replaced.push_str(&s[pushed..start]);
+        replaced.push_str(to);
+        pushed = end;
+    }
+    replaced.push_str(&s[pushed..]);
+
+    replaced
+}
+
+/// Joining slices of strings into a `SmallString`.
+///
+/// This is a stable alternative of `std::slice::Join`, which returns a `SmallString`.
+pub trait Join {
+    /// Concatenates the strings, placing the separator between each.
+    fn join_small(&self, sep: &str) -> SmallString;
+
+    /// Concatenates the strings.
+    fn concat_small(&self) -> SmallString;
+}
+
+impl<S: AsRef<str>> Join for [S] {
+    #[inline]
+    fn join_small(&self, sep: &str) -> SmallString {
+        SmallString::join(self, sep)
+    }
+
+    #[inline]
+    fn concat_small(&self) -> SmallString {
+        SmallString::concat(self)
+    }
+}
+
+/// Conversion into a `SmallString`.
+///
+/// This is intended to be used as a bound of setter arguments, such as
+/// `fn set_id(&mut self, id: impl IntoSmallString)`.
+///
+/// Owned heap strings are adopted without copying if they are too long to be inlined,
+/// and short strings are copied into the inline buffer if the backend supports it.
+pub trait IntoSmallString {
+    /// Converts the value into a `SmallString`.
+    fn into_small_string(self) -> SmallString;
+}
+
+impl IntoSmallString for SmallString {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self
+    }
+}
+
+impl IntoSmallString for &'_ SmallString {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.clone()
+    }
+}
+
+impl IntoSmallString for &'_ str {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for String {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for &'_ String {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.as_str().into()
+    }
+}
+
+impl IntoSmallString for Box<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into()
+    }
+}
+
+impl IntoSmallString for Cow<'_, str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        match self {
+            Cow::Borrowed(s) => s.into(),
+            Cow::Owned(s) => s.into(),
+        }
+    }
+}
+
+impl IntoSmallString for SmallCow<'_> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        self.into_owned()
+    }
+}
+
+impl IntoSmallString for Arc<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        (*self).into()
+    }
+}
+
+impl IntoSmallString for Rc<str> {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        (*self).into()
+    }
+}
+
+impl IntoSmallString for char {
+    #[inline]
+    fn into_small_string(self) -> SmallString {
+        let mut s = SmallString::with_capacity(self.len_utf8());
+        s.push(self);
+        s
+    }
+}
+
+macro_rules! impl_into_small_string_for_display {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSmallString for $ty {
+                #[inline]
+                fn into_small_string(self) -> SmallString {
+                    use std::fmt::Write;
+
+                    let mut s = SmallString::new();
+                    write!(s, "{}", self).expect("Should never fail: `SmallString` never fails to write");
+                    s
+                }
+            }
+        )*
+    };
+}
+
+impl_into_small_string_for_display!(f32, f64);
+
+macro_rules! impl_into_small_string_for_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoSmallString for $ty {
+                #[inline]
+                fn into_small_string(self) -> SmallString {
+                    SmallString::from_dec(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_small_string_for_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    thread_local! {
+        /// Number of allocations and reallocations on the current thread.
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Allocator which counts allocations and reallocations.
+    struct CountingAlloc;
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    /// Calls the function, and returns the result and the number of allocations.
+    fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+        (result, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    /// Returns a heap string longer than `INLINE_CAPACITY`.
+    fn heap_string(capacity: usize) -> SmallString {
+        let mut s = String::with_capacity(capacity);
+        s.push_str("a string too long to be inlined");
+        assert!(s.len() > INLINE_CAPACITY);
+        SmallString::from_string_with(InlinePolicy::AlwaysAdopt, s)
+    }
+
+    #[test]
+    fn take_never_allocates() {
+        let mut s = heap_string(64);
+        let ptr = s.as_ptr();
+        let (taken, allocs) = count_allocations(|| s.take());
+        assert_eq!(allocs, 0);
+        assert_eq!(taken.as_ptr(), ptr);
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn into_string_adopts_heap_buffer() {
+        let s = heap_string(64);
+        let ptr = s.as_ptr();
+        let (string, allocs) = count_allocations(|| s.into_string());
+        assert_eq!(allocs, 0);
+        assert_eq!(string.as_ptr(), ptr);
+        assert_eq!(string.capacity(), 64);
+    }
+
+    #[test]
+    fn replacen_in_place_searches_once() {
+        let mut calls = 0;
+        let mut s = SmallString::from("a-b-c");
+        s.replacen_in_place(
+            |ch: char| {
+                calls += 1;
+                ch == '-'
+            },
+            "+",
+            1,
+        );
+        assert_eq!(s.as_str(), "a+b-c");
+        assert_eq!(calls, 2);
+
+        let mut s = SmallString::from("abc");
+        s.replace_in_place("", "-");
+        assert_eq!(s.as_str(), "-a-b-c-");
+    }
+
+    #[test]
+    fn with_c_str_does_not_allocate_for_short_string() {
+        let s = SmallString::from("file.png");
+        let (len, allocs) = count_allocations(|| s.with_c_str(|c| c.to_bytes_with_nul().len()));
+        assert_eq!(allocs, 0);
+        assert_eq!(len.unwrap(), 9);
+        assert!(SmallString::from("a\0b").with_c_str(|_| ()).is_err());
+    }
+
+    #[test]
+    fn into_boxed_str_adopts_exact_capacity_buffer() {
+        let s = heap_string(0);
+        assert_eq!(s.capacity(), s.len());
+        let ptr = s.as_ptr();
+        let (boxed, allocs) = count_allocations(|| Box::<str>::from(s));
+        assert_eq!(allocs, 0);
+        assert_eq!(boxed.as_ptr(), ptr);
+    }
+}
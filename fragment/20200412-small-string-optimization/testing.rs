@@ -0,0 +1,121 @@
+//! Integrations with property testing and fuzzing libraries.
+//!
+//! Generated strings have lengths biased around the inline capacity, and
+//! multi-byte characters are placed across the boundary, in order to exercise
+//! transitions between inline and heap strings.
+
+#![cfg(any(feature = "arbitrary", feature = "proptest", feature = "quickcheck"))]
+
+use crate::string::{SmallString, INLINE_CAPACITY};
+
+/// ASCII characters to generate.
+const ASCII_CHARS: &[char] = &['a', 'z', 'A', '0', '9', '-', '_', ' ', ':', '\n'];
+
+/// Non-ASCII characters to generate, with 2, 3, and 4 bytes in UTF-8.
+const NON_ASCII_CHARS: &[char] = &['é', 'ß', 'あ', '€', '\u{FFFD}', '😀', '\u{10FFFF}'];
+
+/// Chooses an element of the slice.
+#[inline]
+fn choose_from<T: Copy, E>(
+    choose: &mut impl FnMut(u32) -> Result<u32, E>,
+    items: &[T],
+) -> Result<T, E> {
+    Ok(items[choose(items.len() as u32)? as usize])
+}
+
+/// Chooses a character, ASCII or not.
+#[inline]
+fn choose_char<E>(choose: &mut impl FnMut(u32) -> Result<u32, E>) -> Result<char, E> {
+    if choose(2)? == 0 {
+        choose_from(choose, ASCII_CHARS)
+    } else {
+        choose_from(choose, NON_ASCII_CHARS)
+    }
+}
+
+/// Generates a string.
+///
+/// `choose(n)` should return a value in `0..n`.
+pub(crate) fn generate<E>(mut choose: impl FnMut(u32) -> Result<u32, E>) -> Result<SmallString, E> {
+    let cap = INLINE_CAPACITY;
+    let mut s = SmallString::new();
+
+    // Length to generate, in bytes.
+    // Simpler cases come first, so that shrinking the choices towards zero simplifies the string.
+    let len = match choose(4)? {
+        // Short.
+        0 => choose(cap as u32 + 1)? as usize,
+        // Around the boundary.
+        1 => (cap + choose(7)? as usize).saturating_sub(3),
+        // A multi-byte character across the boundary.
+        2 => {
+            let ch = choose_from(&mut choose, NON_ASCII_CHARS)?;
+            // Leave 1 to `len_utf8() - 1` bytes of room before the boundary.
+            let room = 1 + choose(ch.len_utf8() as u32 - 1)? as usize;
+            let prefix_len = cap.saturating_sub(room);
+            for _ in 0..prefix_len {
+                s.push(choose_from(&mut choose, ASCII_CHARS)?);
+            }
+            s.push(ch);
+            s.len() + choose(4)? as usize
+        }
+        // Long.
+        _ => cap + choose(cap as u32 * 4 + 1)? as usize,
+    };
+
+    while s.len() < len {
+        s.push(choose_char(&mut choose)?);
+    }
+
+    Ok(s)
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for SmallString {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        generate(|n| u.int_in_range(0..=(n - 1)))
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for SmallString {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let result: Result<_, std::convert::Infallible> =
+            generate(|n| Ok(<u32 as quickcheck::Arbitrary>::arbitrary(g) % n));
+        match result {
+            Ok(s) => s,
+            Err(never) => match never {},
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(quickcheck::Arbitrary::shrink(&String::from(self.as_str())).map(Self::from))
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for SmallString {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::strategy::Strategy;
+
+        // Shrinking the choices towards zero makes the string shorter and simpler:
+        // the choice `0` selects a short string, an ASCII character, and so on.
+        // The number of choices is enough for the longest string:
+        // each character consumes up to 3 choices.
+        let max_choices = 4 + (INLINE_CAPACITY * 5 + 4) * 3;
+        proptest::collection::vec(proptest::arbitrary::any::<u32>(), max_choices)
+            .prop_map(|choices| {
+                let mut choices = choices.into_iter();
+                let result: Result<_, std::convert::Infallible> =
+                    generate(|n| Ok(choices.next().unwrap_or(0) % n));
+                match result {
+                    Ok(s) => s,
+                    Err(never) => match never {},
+                }
+            })
+            .boxed()
+    }
+}
@@ -0,0 +1,819 @@
+//! Pointer-sized string type.
+//!
+//! `ThinSmallString` is a single non-null pointer: short strings are stored in
+//! the pointer itself, and longer strings are stored in a heap block which
+//! starts with the length and the capacity.
+//! `Option<ThinSmallString>` is also pointer-sized.
+
+use std::{
+    alloc::{self, Layout},
+    borrow::{Borrow, Cow},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::{FromIterator, FusedIterator},
+    marker::PhantomData,
+    mem,
+    ops::{Bound, Deref, Range, RangeBounds},
+    ptr::{self, NonNull},
+    slice, str,
+};
+
+use crate::string::SmallString;
+
+/// Size of the pointer in bytes.
+const WORD_BYTES: usize = mem::size_of::<usize>();
+
+/// Maximum length of strings stored inline.
+pub const THIN_INLINE_CAPACITY: usize = WORD_BYTES - 1;
+
+/// Position of the tag byte in the inline representation.
+///
+/// The tag byte contains the least significant bit of the pointer.
+#[cfg(target_endian = "little")]
+const TAG_INDEX: usize = 0;
+
+/// Position of the tag byte in the inline representation.
+///
+/// The tag byte contains the least significant bit of the pointer.
+#[cfg(target_endian = "big")]
+const TAG_INDEX: usize = WORD_BYTES - 1;
+
+/// Start position of the content in the inline representation.
+#[cfg(target_endian = "little")]
+const INLINE_START: usize = 1;
+
+/// Start position of the content in the inline representation.
+#[cfg(target_endian = "big")]
+const INLINE_START: usize = 0;
+
+/// Minimum capacity of heap blocks.
+const MIN_HEAP_CAPACITY: usize = 16;
+
+/// Header of heap blocks, followed by the content.
+#[repr(C)]
+struct Header {
+    /// Length of the content.
+    len: usize,
+    /// Capacity of the content.
+    cap: usize,
+}
+
+/// Size of the header.
+const HEADER_SIZE: usize = mem::size_of::<Header>();
+
+/// Returns the layout of a heap block with the given capacity.
+#[inline]
+fn heap_layout(cap: usize) -> Layout {
+    HEADER_SIZE
+        .checked_add(cap)
+        .and_then(|size| Layout::from_size_align(size, mem::align_of::<Header>()).ok())
+        .expect("capacity overflow")
+}
+
+/// Allocates a heap block with the given capacity, and copies the content into it.
+fn alloc_heap(cap: usize, content: &[u8]) -> NonNull<u8> {
+    debug_assert!(content.len() <= cap);
+    let layout = heap_layout(cap);
+    // SAFETY: The layout has non-zero size since it contains the header.
+    let ptr = unsafe { alloc::alloc(layout) };
+    let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+    debug_assert_eq!(
+        ptr.as_ptr() as usize & 1,
+        0,
+        "Heap blocks should be aligned"
+    );
+    // SAFETY: The block is large enough for the header and `cap` bytes of content,
+    // and aligned for the header.
+    unsafe {
+        ptr.as_ptr().cast::<Header>().write(Header {
+            len: content.len(),
+            cap,
+        });
+        ptr::copy_nonoverlapping(
+            content.as_ptr(),
+            ptr.as_ptr().add(HEADER_SIZE),
+            content.len(),
+        );
+    }
+    ptr
+}
+
+/// Pointer-sized owned string type.
+///
+/// Strings up to [`THIN_INLINE_CAPACITY`] bytes (7 bytes on 64-bit platforms)
+/// are stored inline.
+///
+/// The pointer is tagged by its least significant bit: heap blocks are aligned,
+/// so `1` means the inline representation, where the tag byte has the length
+/// in the other bits and the other bytes have the content.
+///
+/// [`THIN_INLINE_CAPACITY`]: constant.THIN_INLINE_CAPACITY.html
+pub struct ThinSmallString {
+    /// Tagged pointer.
+    ptr: NonNull<u8>,
+}
+
+// SAFETY: The heap block is uniquely owned as `Box<str>` is.
+unsafe impl Send for ThinSmallString {}
+
+// SAFETY: The heap block is never modified through shared references.
+unsafe impl Sync for ThinSmallString {}
+
+impl ThinSmallString {
+    /// Creates a new empty string.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            ptr: Self::encode_inline([0; WORD_BYTES], 0),
+        }
+    }
+
+    /// Creates a new empty string with a particular capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity <= THIN_INLINE_CAPACITY {
+            Self::new()
+        } else {
+            Self {
+                ptr: alloc_heap(capacity, &[]),
+            }
+        }
+    }
+
+    /// Returns the tagged pointer for the inline representation.
+    ///
+    /// The tag byte of `bytes` is overwritten.
+    #[inline]
+    fn encode_inline(mut bytes: [u8; WORD_BYTES], len: usize) -> NonNull<u8> {
+        debug_assert!(len <= THIN_INLINE_CAPACITY);
+        bytes[TAG_INDEX] = ((len as u8) << 1) | 1;
+        let addr = usize::from_ne_bytes(bytes);
+        // The pointer is never dereferenced, so it has no provenance.
+        NonNull::new(ptr::null_mut::<u8>().wrapping_add(addr))
+            .expect("Should never fail: the tag bit is set")
+    }
+
+    /// Returns `true` if the string is stored inline.
+    #[inline]
+    fn is_inline(&self) -> bool {
+        self.ptr.as_ptr() as usize & 1 != 0
+    }
+
+    /// Returns the bytes of the inline representation.
+    #[inline]
+    fn inline_bytes(&self) -> &[u8; WORD_BYTES] {
+        debug_assert!(self.is_inline());
+        // SAFETY: The pointer and the array have the same size, and the array has
+        // no alignment requirement and no invalid values.
+        unsafe { &*(&self.ptr as *const NonNull<u8>).cast::<[u8; WORD_BYTES]>() }
+    }
+
+    /// Returns the header of the heap block.
+    #[inline]
+    fn header(&self) -> &Header {
+        debug_assert!(!self.is_inline());
+        // SAFETY: Untagged pointers always point to live heap blocks.
+        unsafe { &*self.ptr.as_ptr().cast::<Header>() }
+    }
+
+    /// Returns the header of the heap block.
+    #[inline]
+    fn header_mut(&mut self) -> &mut Header {
+        debug_assert!(!self.is_inline());
+        // SAFETY: Untagged pointers always point to live heap blocks, which are
+        // uniquely owned.
+        unsafe { &mut *self.ptr.as_ptr().cast::<Header>() }
+    }
+
+    /// Returns the length in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        if self.is_inline() {
+            usize::from(self.inline_bytes()[TAG_INDEX] >> 1)
+        } else {
+            self.header().len
+        }
+    }
+
+    /// Returns `true` if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity in bytes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        if self.is_inline() {
+            THIN_INLINE_CAPACITY
+        } else {
+            self.header().cap
+        }
+    }
+
+    /// Returns the content as bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.is_inline() {
+            let len = self.len();
+            &self.inline_bytes()[INLINE_START..(INLINE_START + len)]
+        } else {
+            // SAFETY: The heap block has `len` bytes of initialized content
+            // after the header.
+            unsafe { slice::from_raw_parts(self.ptr.as_ptr().add(HEADER_SIZE), self.header().len) }
+        }
+    }
+
+    /// Returns a string slice.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: The content is always valid UTF-8.
+        unsafe { str::from_utf8_unchecked(self.as_bytes()) }
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let cap = self.capacity();
+        if required <= cap {
+            return;
+        }
+
+        let new_cap = required.max(cap.saturating_mul(2)).max(MIN_HEAP_CAPACITY);
+        if self.is_inline() {
+            self.ptr = alloc_heap(new_cap, self.as_bytes());
+            return;
+        }
+
+        let new_layout = heap_layout(new_cap);
+        // SAFETY: The block was allocated with the layout for `cap`, and the new
+        // size is non-zero.
+        let ptr = unsafe { alloc::realloc(self.ptr.as_ptr(), heap_layout(cap), new_layout.size()) };
+        self.ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        self.header_mut().cap = new_cap;
+    }
+
+    /// Shrinks the capacity to match the length.
+    ///
+    /// Short strings are moved back inline.
+    pub fn shrink_to_fit(&mut self) {
+        if self.is_inline() || self.len() == self.capacity() {
+            return;
+        }
+        let shrunk = if self.len() <= THIN_INLINE_CAPACITY {
+            Self::from(self.as_str())
+        } else {
+            Self {
+                ptr: alloc_heap(self.len(), self.as_bytes()),
+            }
+        };
+        *self = shrunk;
+    }
+
+    /// Appends the given string.
+    pub fn push_str(&mut self, s: &str) {
+        let len = self.len();
+        let new_len = len.checked_add(s.len()).expect("capacity overflow");
+        if self.is_inline() && new_len <= THIN_INLINE_CAPACITY {
+            let mut bytes = *self.inline_bytes();
+            bytes[(INLINE_START + len)..(INLINE_START + new_len)].copy_from_slice(s.as_bytes());
+            self.ptr = Self::encode_inline(bytes, new_len);
+            return;
+        }
+
+        self.reserve(s.len());
+        // SAFETY: The string is on the heap after the reservation, and the heap
+        // block has enough space for the new content.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                s.as_ptr(),
+                self.ptr.as_ptr().add(HEADER_SIZE + len),
+                s.len(),
+            );
+        }
+        self.header_mut().len = new_len;
+    }
+
+    /// Appends the given character.
+    #[inline]
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Shortens the string to the given length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not lie on a char boundary.
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len >= self.len() {
+            return;
+        }
+        assert!(
+            self.as_str().is_char_boundary(new_len),
+            "new length is not on a char boundary"
+        );
+        if self.is_inline() {
+            let mut bytes = *self.inline_bytes();
+            // Keep unused bytes zero.
+            for b in &mut bytes[(INLINE_START + new_len)..(INLINE_START + THIN_INLINE_CAPACITY)] {
+                *b = 0;
+            }
+            self.ptr = Self::encode_inline(bytes, new_len);
+        } else {
+            self.header_mut().len = new_len;
+        }
+    }
+
+    /// Removes the last character and returns it.
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        let ch = self.as_str().chars().next_back()?;
+        self.truncate(self.len() - ch.len_utf8());
+        Some(ch)
+    }
+
+    /// Truncates the string to zero length, keeping the capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+
+    /// Inserts the string at the given byte position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the length, or if it does not lie on a char boundary.
+    pub fn insert_str(&mut self, idx: usize, string: &str) {
+        // Inefficient but fully safe.
+        let tail = Self::from(&self[idx..]);
+        self.truncate(idx);
+        self.push_str(string);
+        self.push_str(&tail);
+    }
+
+    /// Inserts the character at the given byte position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is larger than the length, or if it does not lie on a char boundary.
+    #[inline]
+    pub fn insert(&mut self, idx: usize, ch: char) {
+        self.insert_str(idx, ch.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes the character at the given byte position and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not less than the length, or if it does not lie on a char boundary.
+    pub fn remove(&mut self, idx: usize) -> char {
+        let ch = self[idx..]
+            .chars()
+            .next()
+            .expect("cannot remove a char from the end of a string");
+        self.replace_range(idx..(idx + ch.len_utf8()), "");
+        ch
+    }
+
+    /// Retains only the characters which the predicate returns `true` for.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(char) -> bool,
+    {
+        // Inefficient but fully safe.
+        let retained: Self = self.chars().filter(|&ch| f(ch)).collect();
+        self.clear();
+        self.push_str(&retained);
+    }
+
+    /// Splits the string into two at the given byte position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is larger than the length, or if it does not lie on a char boundary.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let tail = Self::from(&self[at..]);
+        self.truncate(at);
+        tail
+    }
+
+    /// Replaces the given range with the given string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the range does not lie on a char boundary,
+    /// or if they are out of bounds.
+    pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        // Inefficient but fully safe.
+        let range = resolve_range(range, self.len());
+        let tail = Self::from(&self[range.end..]);
+        assert!(
+            self.as_str().is_char_boundary(range.start),
+            "range start is not on a char boundary"
+        );
+        self.truncate(range.start);
+        self.push_str(replace_with);
+        self.push_str(&tail);
+    }
+
+    /// Removes the given range and returns the removed characters as an iterator.
+    ///
+    /// The range is removed even if the iterator is not consumed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start or the end of the range does not lie on a char boundary,
+    /// or if they are out of bounds.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = resolve_range(range, self.len());
+        let removed = Self::from(&self[range.clone()]);
+        self.replace_range(range, "");
+
+        Drain {
+            back: removed.len(),
+            removed,
+            front: 0,
+            _string: PhantomData,
+        }
+    }
+
+    /// Converts this string into a `String`.
+    #[inline]
+    pub fn into_string(self) -> String {
+        String::from(self.as_str())
+    }
+}
+
+impl Drop for ThinSmallString {
+    fn drop(&mut self) {
+        if !self.is_inline() {
+            let layout = heap_layout(self.capacity());
+            // SAFETY: The block was allocated with the layout for its capacity.
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), layout) }
+        }
+    }
+}
+
+impl Clone for ThinSmallString {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::from(self.as_str())
+    }
+}
+
+impl Default for ThinSmallString {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for ThinSmallString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for ThinSmallString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for ThinSmallString {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Borrow<str> for ThinSmallString {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for ThinSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for ThinSmallString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Write for ThinSmallString {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+impl PartialEq for ThinSmallString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        // Inline strings are equal if and only if the pointers are equal,
+        // since unused bytes are always zero.
+        self.ptr == other.ptr || self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ThinSmallString {}
+
+impl PartialOrd for ThinSmallString {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ThinSmallString {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for ThinSmallString {
+    /// Hashes the string as `str` does.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+macro_rules! impl_cmp {
+    ($other:ty) => {
+        impl PartialEq<$other> for ThinSmallString {
+            #[inline]
+            fn eq(&self, other: &$other) -> bool {
+                PartialEq::eq(self.as_str(), &other[..])
+            }
+        }
+
+        impl PartialEq<ThinSmallString> for $other {
+            #[inline]
+            fn eq(&self, other: &ThinSmallString) -> bool {
+                PartialEq::eq(&self[..], other.as_str())
+            }
+        }
+    };
+}
+
+impl_cmp!(str);
+impl_cmp!(&str);
+impl_cmp!(String);
+impl_cmp!(SmallString);
+impl_cmp!(Cow<'_, str>);
+
+impl From<&str> for ThinSmallString {
+    fn from(s: &str) -> Self {
+        let mut string = Self::with_capacity(s.len());
+        string.push_str(s);
+        string
+    }
+}
+
+impl From<String> for ThinSmallString {
+    /// Converts the `String`.
+    ///
+    /// The heap buffer of the `String` cannot be adopted, so the content is copied.
+    #[inline]
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<SmallString> for ThinSmallString {
+    #[inline]
+    fn from(s: SmallString) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<Box<str>> for ThinSmallString {
+    #[inline]
+    fn from(s: Box<str>) -> Self {
+        Self::from(&*s)
+    }
+}
+
+impl From<Cow<'_, str>> for ThinSmallString {
+    #[inline]
+    fn from(s: Cow<'_, str>) -> Self {
+        Self::from(&*s)
+    }
+}
+
+impl From<ThinSmallString> for String {
+    #[inline]
+    fn from(s: ThinSmallString) -> Self {
+        s.into_string()
+    }
+}
+
+impl From<ThinSmallString> for SmallString {
+    #[inline]
+    fn from(s: ThinSmallString) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<ThinSmallString> for Box<str> {
+    #[inline]
+    fn from(s: ThinSmallString) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for ThinSmallString {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl FromIterator<char> for ThinSmallString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut s = Self::new();
+        s.extend(iter);
+        s
+    }
+}
+
+impl<'a> Extend<&'a str> for ThinSmallString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|s| self.push_str(s));
+    }
+}
+
+impl Extend<char> for ThinSmallString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|ch| self.push(ch));
+    }
+}
+
+/// Converts the range bounds into a range, checking it is in `0..=len`.
+fn resolve_range<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&n) => n.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&n) => n,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range start is greater than range end");
+    assert!(end <= len, "range end out of bounds");
+
+    start..end
+}
+
+/// Draining iterator for `ThinSmallString`.
+#[derive(Debug)]
+pub struct Drain<'a> {
+    /// Removed string.
+    removed: ThinSmallString,
+    /// Start position of characters not yet iterated.
+    front: usize,
+    /// End position of characters not yet iterated.
+    back: usize,
+    /// Marker for compatibility with `std::string::Drain`.
+    _string: PhantomData<&'a mut ThinSmallString>,
+}
+
+impl Iterator for Drain<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        let ch = self.removed[self.front..self.back].chars().next()?;
+        self.front += ch.len_utf8();
+        Some(ch)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.removed[self.front..self.back].chars().size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Drain<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<char> {
+        let ch = self.removed[self.front..self.back].chars().next_back()?;
+        self.back -= ch.len_utf8();
+        Some(ch)
+    }
+}
+
+impl FusedIterator for Drain<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ASCII string longer than the inline capacity.
+    const LONG: &str = "abcdefghijklmnopqrstuvwxyz";
+
+    #[test]
+    fn switches_from_inline_to_heap() {
+        let mut s = ThinSmallString::new();
+        for ch in LONG[..THIN_INLINE_CAPACITY].chars() {
+            s.push(ch);
+            assert!(s.is_inline());
+        }
+        assert_eq!(s.capacity(), THIN_INLINE_CAPACITY);
+
+        s.push('!');
+        assert!(!s.is_inline());
+        assert!(s.capacity() >= MIN_HEAP_CAPACITY);
+        assert_eq!(s.len(), THIN_INLINE_CAPACITY + 1);
+        assert_eq!(&s[..THIN_INLINE_CAPACITY], &LONG[..THIN_INLINE_CAPACITY]);
+        assert!(s.ends_with('!'));
+
+        assert!(ThinSmallString::with_capacity(THIN_INLINE_CAPACITY).is_inline());
+        assert!(!ThinSmallString::with_capacity(THIN_INLINE_CAPACITY + 1).is_inline());
+    }
+
+    #[test]
+    fn truncate_zeroes_inline_tail() {
+        let mut s = ThinSmallString::from(&LONG[..THIN_INLINE_CAPACITY]);
+        assert!(s.is_inline());
+        s.truncate(2);
+        let bytes = s.inline_bytes();
+        assert!(
+            bytes[(INLINE_START + 2)..(INLINE_START + THIN_INLINE_CAPACITY)]
+                .iter()
+                .all(|&b| b == 0)
+        );
+        assert_eq!(s.ptr, ThinSmallString::from("ab").ptr);
+
+        s.clear();
+        assert_eq!(s.ptr, ThinSmallString::new().ptr);
+    }
+
+    #[test]
+    fn shrink_to_fit_moves_back_inline() {
+        let mut s = ThinSmallString::from(LONG);
+        s.truncate(3);
+        assert!(!s.is_inline());
+        s.shrink_to_fit();
+        assert!(s.is_inline());
+        assert_eq!(s.as_str(), "abc");
+        assert_eq!(s.ptr, ThinSmallString::from("abc").ptr);
+
+        let mut s = ThinSmallString::with_capacity(LONG.len() * 2);
+        s.push_str(LONG);
+        s.shrink_to_fit();
+        assert!(!s.is_inline());
+        assert_eq!(s.capacity(), LONG.len());
+        assert_eq!(s.as_str(), LONG);
+    }
+
+    #[test]
+    fn inline_strings_are_compared_by_pointer() {
+        let a = ThinSmallString::from("ab");
+        let mut b = ThinSmallString::from("abc");
+        b.pop();
+        assert_eq!(a.ptr, b.ptr);
+        assert_eq!(a, b);
+        assert_ne!(a, ThinSmallString::from("ac"));
+        assert_ne!(a, ThinSmallString::from("a"));
+
+        // A heap string equals an inline string with the same content.
+        let mut heap = ThinSmallString::with_capacity(LONG.len());
+        heap.push_str("ab");
+        assert!(!heap.is_inline());
+        assert_ne!(a.ptr, heap.ptr);
+        assert_eq!(a, heap);
+        assert_eq!(heap, a);
+    }
+}